@@ -10,21 +10,27 @@ use typing::typed_term::{TyDecl, TyFieldAccess, TyFnApp, TyFnAppArg, TyFnDecl, T
                             TyGraphDecl, TyNodeDecl, TyTerm, TyUseStmt, TyWeightsAssign,
                             TyWeightsDecl, TyAliasAssign};
 use typing::Type;
+use parsing::term::Pattern;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::fmt::Write;
-use std::collections::{ BTreeSet, BTreeMap, VecDeque };
+use std::collections::{ BTreeSet, BTreeMap };
 use errors::{Diag, Emitter};
 use core::Core;
 
-type VarName = String;
-type FnName = String;
+fn fn_param_names(func: &TyFnDecl) -> Vec<String> {
+    func.fn_params
+        .iter()
+        .map(|p| p.name.to_owned())
+        .collect()
+}
 
-enum Item {
-    FnApp(Option<VarName>, FnName, Vec<TyFnAppArg>, bool, Option<ModName>),
-    SelfFnApp(Option<VarName>, FnName, Vec<TyFnAppArg>),
-    Ident(bool, String),
-    ViewFn(Option<VarName>, Type),
+/// what to do with the value of an `if` branch
+#[derive(Clone, Copy, PartialEq)]
+enum Branch {
+    Stmt,
+    Return,
+    Assign,
 }
 
 pub struct Module {
@@ -36,7 +42,7 @@ pub struct Module {
     pub inits: Rc<RefCell<Vec<TyWeightsAssign>>>,
     pub buf: String,
     pub indent: usize,
-    codegen_stack: VecDeque<Item>,
+    tmp_counter: usize,
 }
 
 impl Module {
@@ -50,7 +56,7 @@ impl Module {
             fns: Rc::new(RefCell::new(BTreeMap::new())),
             inits: Rc::new(RefCell::new(vec![])),
             indent: 0,
-            codegen_stack: VecDeque::new(),
+            tmp_counter: 0,
         }
     }
 
@@ -101,194 +107,293 @@ impl Module {
         Ok(())
     }
 
-    fn collect_term(&mut self, term: &TyTerm, var: Option<String>, is_stmt: bool) -> Result<(), Diag> {
+    /// create a new python variable name for intermediate values
+    fn fresh_tmp(&mut self) -> String {
+        let ret = format!("_t{}", self.tmp_counter);
+        self.tmp_counter += 1;
+        ret
+    }
+
+    /// generate the statements of a function body.
+    /// `var` holds the intermediate values of a pipeline
+    fn gen_fn_body(&mut self, term: &TyTerm, var: &str, is_ret: bool) -> Result<(), Diag> {
         use self::TyTerm::*;
         match term {
             TyBlock{stmts, ret, ..} => {
-                self.collect_term(&stmts, var.clone(), is_stmt)?;
-                self.collect_term(&ret, var, is_stmt)?;
+                self.gen_stmt(&stmts, var)?;
+                if is_ret {
+                    self.gen_ret(&ret, var)?;
+                } else {
+                    self.gen_stmt(&ret, var)?;
+                }
             }
+            _ => panic!("{:#?}", term),
+        }
+        Ok(())
+    }
+
+    fn gen_stmt(&mut self, term: &TyTerm, var: &str) -> Result<(), Diag> {
+        use self::TyTerm::*;
+        match term {
             TyList(terms) => terms
                 .iter()
-                .map(|t| self.collect_term(t, var.clone(), is_stmt))
+                .map(|t| self.gen_stmt(t, var))
                 .collect::<Result<_,_>>()?,
-            TyExpr(t,..) => {
-                self.collect_term(t, var, is_stmt)?;
+            TyStmt(t, _) => self.gen_stmt(t, var)?,
+            TyConditional{cond, then, els, ..} => self.gen_if("if", cond, then, els, var, Branch::Stmt)?,
+            // intermediate values of the bound expression are written to the bound name
+            TyLet(_, name, rhs, _) | TyAssign(_, name, rhs, _) => {
+                let out = self.gen_expr(rhs, name.as_str())?;
+                if out != name.as_str() {
+                    self.indent()?;
+                    writeln!(self.buf, "{} = {}", name.as_str(), out)?;
+                }
+            }
+            TyLetTuple(_, items, rhs, _) => {
+                let first = items[0].names().remove(0);
+                let out = self.gen_expr(rhs, &first)?;
+                self.indent()?;
+                let items = items.iter().map(gen_pattern).collect::<Vec<_>>();
+                writeln!(self.buf, "{} = {}", items.join(", "), out)?;
+            }
+            TyForLoop{var: i, from, to, body, ..} => {
+                self.indent()?;
+                if *from == 0 {
+                    writeln!(self.buf, "for {} in range({}):", i.as_str(), to)?;
+                } else {
+                    writeln!(self.buf, "for {} in range({}, {}):", i.as_str(), from, to)?;
+                }
+                self.gen_branch(body, var, Branch::Stmt)?;
             }
-            TyFnApp(box fn_app) => {
-                self.collect_fn_app(fn_app, var, is_stmt)?;
-            },
-            TyIdent(_t,i,..) => self.codegen_stack
-                .push_back(Item::Ident(var.is_none(), i.as_str().to_owned())),
-            TyInteger(..) => (),
-            TyFloat(..) => (),
-            TyStmt(t, _) => self.collect_term(t, var, true)?,
             TyNone => (),
-            _ => panic!("{:#?}", term),
+            _ => {
+                let out = self.gen_expr(term, var)?;
+                if !is_atomic(term) {
+                    self.indent()?;
+                    writeln!(self.buf, "{}", out)?;
+                }
+            }
         }
         Ok(())
     }
 
-    fn generate_fn(&mut self) -> Result<(), Diag> {
-        while let Some(item) = self.codegen_stack.pop_back() {
-            match item {
-                Item::FnApp(var_name, fn_name, args, is_stmt, mod_name) => {
-                    self.indent()?;
-                    let mut is_global = false;
-                    let module_name = match mod_name {
-                        None =>
-                            self.tenv.borrow()
-                            .resolve_type(
-                                &ModName::Named(self.name.to_owned()),
-                                &Alias::Variable(fn_name.to_owned()),
-                            )
-                            .unwrap_or_else(|| {
-                                is_global = true;
-                                self.tenv.borrow()
-                                    .resolve_type(
-                                        &ModName::Global,
-                                        &Alias::Variable(fn_name.to_owned()),
-                                    ).unwrap()
-                            })
-                            .as_string(),
-                        Some(ref mn) => mn.as_str().to_owned(),
-                    };
-
-                    match (var_name, is_stmt) {
-                        (Some(v), false) => write!(self.buf, "{} = ", v)?,  // assignment
-                        (None, false) => write!(self.buf, "return ")?,      // return
-                        (None, true) => write!(self.buf, "")?,              // 
-                        (Some(_), true) => write!(self.buf, "")?,           // nn.init.normal_(..)
-                    };
-
-                    let core_cloned = self.core.clone();
-                    let core = core_cloned.borrow();
-                    let op = core.find_mod(&module_name).unwrap();
-                    let out = {
-                        if mod_name.is_some() {
-                            op.gen_fn_app(&fn_name, args.as_slice())?
-                        } else {
-                            op.gen_fn_app("forward", args.as_slice())?
-                        }
-                    };
+    fn gen_ret(&mut self, term: &TyTerm, var: &str) -> Result<(), Diag> {
+        use self::TyTerm::*;
+        match term {
+            TyNone => (),
+            TyIdent(_, ref name, _) if name.as_str() == "self" => (),
+            TyConditional{cond, then, els, ..} => self.gen_if("if", cond, then, els, var, Branch::Return)?,
+            _ => {
+                let out = self.gen_expr(term, var)?;
+                self.indent()?;
+                writeln!(self.buf, "return {}", out)?;
+            }
+        }
+        Ok(())
+    }
 
-                    match (is_global, is_stmt) {
-                        (true, false) => {
-                            write!(self.buf, "{}(",  op.pytorch_name())?;
-                            writeln!(self.buf, "{})", out)?
-                        }
-                        (false, false) => {
-                            write!(self.buf, "self.{}(", fn_name)?;
-                            writeln!(self.buf, "{})", out)?
-                        }
-                        (true, true) => write!(self.buf, "")?,
-                        (false, true) => {
-                            write!(self.buf, "")?;
-                            writeln!(self.buf, "{}", out)?;
-                        }
-                    }
-                }
-                Item::SelfFnApp(var_name, fn_name, args) => {
-                    self.indent()?;
-                    match var_name {
-                        Some(v) => write!(self.buf, "{} = ", v)?,
-                        None => write!(self.buf, "return ")?,
-                    };
-                    write!(self.buf, "self.{}(", fn_name)?;
-                    let s = args.to_btreemap().unwrap().keys().cloned().collect::<Vec<_>>().join(", ");
-                    write!(self.buf, "{}", s)?;
-                    writeln!(self.buf, ")")?;
-                }
-                Item::Ident(ret, name) => {
-                    if ret && name != "self" {
-                        self.indent()?;
-                        writeln!(self.buf, "return {}", name)?;
-                    }
-                }
-                Item::ViewFn(var_name, ty) => {
-                    self.indent()?;
-                    match var_name {
-                        Some(name) => {
-                            writeln!(self.buf, "{} = {}.view({})", name, name, ty.as_string())?;
-                        }
-                        None => {
-                            writeln!(self.buf, "return x.view({})", ty.as_string())?;
+    /// generate a python expression, nested function applications
+    /// are first assigned to `var`
+    fn gen_expr(&mut self, term: &TyTerm, var: &str) -> Result<String, Diag> {
+        use self::TyTerm::*;
+        match term {
+            TyExpr(t, ..) => self.gen_expr(t, var),
+            TyIdent(_, i, _) => Ok(i.as_str().to_owned()),
+            TyInteger(..) | TyFloat(..) | TyBool(..) => Ok(term.as_str().unwrap()),
+            TyFieldAccess(f_a) => Ok(format!("{}.{}", f_a.mod_name, f_a.field_name)),
+            TyConditional{cond, then, els, ..} => {
+                let var = if var.is_empty() { self.fresh_tmp() } else { var.to_owned() };
+                self.gen_if("if", cond, then, els, &var, Branch::Assign)?;
+                Ok(var)
+            }
+            TyTuple(_, ts, _) => {
+                let vs = ts
+                    .iter()
+                    .map(|t| self.gen_operand(t))
+                    .collect::<Result<Vec<_>,_>>()?;
+                Ok(format!("({})", vs.join(", ")))
+            }
+            TyFnApp(fn_app) => self.gen_fn_app(fn_app, var),
+            TyBinOp(_, op, lhs, rhs, _) => {
+                let lhs = self.gen_operand(lhs)?;
+                let rhs = self.gen_operand(rhs)?;
+                Ok(format!("{} {} {}", lhs, op.as_str(), rhs))
+            }
+            _ => panic!("{:#?}", term),
+        }
+    }
+
+    /// generate `if`/`elif`/`else`, the value of each branch is handled by `kind`
+    fn gen_if(&mut self, kw: &str, cond: &TyTerm, then: &TyTerm, els: &TyTerm, var: &str, kind: Branch)
+        -> Result<(), Diag>
+    {
+        let cond = self.gen_expr(cond, "")?;
+        self.indent()?;
+        writeln!(self.buf, "{} {}:", kw, cond)?;
+        self.gen_branch(then, var, kind)?;
+        match els {
+            TyTerm::TyNone => (),
+            TyTerm::TyConditional{cond, then, els, ..} => self.gen_if("elif", cond, then, els, var, kind)?,
+            _ => {
+                self.indent()?;
+                writeln!(self.buf, "else:")?;
+                self.gen_branch(els, var, kind)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn gen_branch(&mut self, block: &TyTerm, var: &str, kind: Branch) -> Result<(), Diag> {
+        self.tab();
+        let len = self.buf.len();
+        match block {
+            TyTerm::TyBlock{stmts, ret, ..} => {
+                self.gen_stmt(&stmts, var)?;
+                match kind {
+                    Branch::Stmt => self.gen_stmt(&ret, var)?,
+                    Branch::Return => self.gen_ret(&ret, var)?,
+                    Branch::Assign => {
+                        let out = self.gen_expr(&ret, var)?;
+                        if out != var {
+                            self.indent()?;
+                            writeln!(self.buf, "{} = {}", var, out)?;
                         }
                     }
                 }
             }
+            _ => panic!("{:#?}", block),
         }
+        if self.buf.len() == len {
+            self.indent()?;
+            writeln!(self.buf, "pass")?;
+        }
+        self.shift_tab();
         Ok(())
     }
 
-    fn collect_fn_app(&mut self, fn_app: &TyFnApp, var_name: Option<String>, is_stmt: bool) -> Result<(), Diag> {
-
-        if fn_app.mod_name == Some("view".to_owned()) {
-            self.codegen_stack.push_back(Item::ViewFn(
-                var_name,
-                fn_app.ret_ty.clone(),
-            ))
-        } else if fn_app.name == Alias::Function("forward".to_owned()) {
-            self.codegen_stack.push_back(Item::FnApp(
-                var_name,
-                fn_app.orig_name.clone().unwrap().to_owned(),
-                fn_app.args.clone(),
-                is_stmt,
-                None,
-            ));
+    /// operands are evaluated into their own variables so they
+    /// don't overwrite each other's inputs
+    fn gen_operand(&mut self, term: &TyTerm) -> Result<String, Diag> {
+        let out = if has_nested_app(term) {
+            let tmp = self.fresh_tmp();
+            self.gen_expr(term, &tmp)?
         } else {
-            if fn_app.orig_name == Some("self".to_owned())  {
-                self.codegen_stack.push_back(Item::SelfFnApp(
-                    var_name,
-                    fn_app.name.as_str().to_owned(),
-                    fn_app.args.clone()
-                ));
-            } else { // init_normal
-                let mod_ty = self.tenv.borrow().resolve_type(
-                    &ModName::Named(self.name.to_owned()),
-                    &Alias::Variable(fn_app.orig_name.clone().unwrap().as_str().to_owned()),
-                ).unwrap();
-                self.codegen_stack.push_back(Item::FnApp(
-                    fn_app.mod_name.clone(),
-                    fn_app.name.as_str().to_owned(),
-                    fn_app.args.clone(),
-                    is_stmt,
-                    Some(mod_ty.as_mod_name()),
-                ));
+            self.gen_expr(term, "")?
+        };
+        match strip_expr(term) {
+            TyTerm::TyBinOp(..) => Ok(format!("({})", out)),
+            _ => Ok(out),
+        }
+    }
+
+    fn gen_fn_app(&mut self, fn_app: &TyFnApp, var: &str) -> Result<String, Diag> {
+        // tensor arguments are passed by variable name
+        let mut args = fn_app.shallow().args;
+        for (arg, orig) in args.iter_mut().zip(fn_app.args.iter()) {
+            if is_literal(&orig.arg) {
+                continue;
+            }
+            let out = self.gen_expr(&orig.arg, var)?;
+            if is_atomic(&orig.arg) {
+                arg.name = Some(out);
+            } else {
+                self.indent()?;
+                writeln!(self.buf, "{} = {}", var, out)?;
+                arg.name = Some(var.to_owned());
             }
         }
 
-        for arg in fn_app.args.iter() {
-            self.collect_term(&arg.arg, arg.name.clone(), is_stmt)?;
+        let fn_name = fn_app.name.as_str();
+        let core_cloned = self.core.clone();
+        let core = core_cloned.borrow();
+        if fn_app.mod_name == Some("view".to_owned()) {
+            let input = args[0].name.clone().unwrap();
+            Ok(format!("{}.view({})", input, fn_app.ret_ty.as_string()))
+        } else if fn_app.mod_name == Some("to".to_owned()) {
+            let input = args[0].name.clone().unwrap();
+            match fn_app.ret_ty.dtype() {
+                Some(Type::DTYPE(dtype, _)) => Ok(format!("{}.to({})", input, dtype.as_torch())),
+                _ => unreachable!(),
+            }
+        } else if fn_name == "forward" {
+            let orig_name = fn_app.orig_name.clone().unwrap();
+            let mut is_global = false;
+            let module_name = self.tenv.borrow()
+                .resolve_type(
+                    &ModName::Named(self.name.to_owned()),
+                    &Alias::Variable(orig_name.to_owned()),
+                )
+                .unwrap_or_else(|| {
+                    is_global = true;
+                    self.tenv.borrow()
+                        .resolve_type(
+                            &ModName::Global,
+                            &Alias::Variable(orig_name.to_owned()),
+                        ).unwrap()
+                })
+                .as_string();
+            let out = match core.find_mod(&module_name) {
+                Some(op) => op.gen_fn_app("forward", args.as_slice())?,
+                // user-defined node
+                None => args.iter()
+                    .map(|a| a.name.clone().unwrap())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            if is_global {
+                let op = core.find_mod(&module_name).unwrap();
+                Ok(format!("{}({})", op.pytorch_name(), out))
+            } else if let Some(ref idx) = fn_app.index {
+                let idx = self.gen_expr(idx, "")?;
+                Ok(format!("self.{}[{}]({})", orig_name, idx, out))
+            } else {
+                Ok(format!("self.{}({})", orig_name, out))
+            }
+        } else if fn_app.orig_name == Some("self".to_owned()) {
+            let s = args.to_btreemap().unwrap().keys().cloned().collect::<Vec<_>>().join(", ");
+            Ok(format!("self.{}({})", fn_name, s))
+        } else { // init_normal, init_pretrained
+            let orig_name = fn_app.orig_name.clone().unwrap();
+            let mod_ty = self.tenv.borrow().resolve_type(
+                &ModName::Named(self.name.to_owned()),
+                &Alias::Variable(orig_name.to_owned()),
+            ).unwrap();
+            let op = core.find_mod(mod_ty.as_mod_name().as_str()).unwrap();
+            // the module being initialized goes first
+            args.insert(0, TyFnAppArg {
+                name: Some(format!("self.{}", orig_name)),
+                arg: box TyTerm::TyNone,
+                span: fn_app.span,
+            });
+            op.gen_fn_app(fn_name, args.as_slice())
         }
-        Ok(())
     }
 
     pub fn generate_class_head(&mut self) -> Result<(), Diag> {
         writeln!(self.buf, "class {}(nn.Module):", self.name)?;
         self.tab();
         self.indent()?;
-        writeln!(self.buf, "'''{:?}'''", self.ty)?;
+        writeln!(self.buf, "'''{}'''", fmt_ty_sig(&self.ty))?;
         self.shift_tab();
         Ok(())
     }
 
     fn generate_fn_decl(&mut self, func: &TyFnDecl) -> Result<(), Diag> {
-        self.generate_fn_decl_head(func.name.as_str(), func)?;
+        let params = fn_param_names(func);
+        self.generate_fn_decl_head(func.name.as_str(), &params)?;
         self.tab();
         // self.indent()?;
         // writeln!(self.buf, "'''{:?}'''", func.fn_ty)?;
-        self.collect_term(&func.func_block, None, false)?;
-        self.generate_fn()?;
+        let var = func.fn_params
+            .get(0)
+            .map(|p| p.name.to_owned())
+            .unwrap_or_else(|| "x".to_owned());
+        self.gen_fn_body(&func.func_block, &var, true)?;
         self.shift_tab();
         Ok(())
     }
 
-    fn generate_fn_decl_head(&mut self, name: &str, func: &TyFnDecl) -> Result<(), Diag> {
-        let params = func.fn_params
-            .iter()
-            .map(|p| format!("{}", p.name))
-            .collect::<Vec<_>>();
+    fn generate_fn_decl_head(&mut self, name: &str, params: &[String]) -> Result<(), Diag> {
         self.indent()?;
         if !params.is_empty() {
             writeln!(self.buf, "def {}(self, {}):", name, params.join(", "))?;
@@ -299,7 +404,10 @@ impl Module {
     }
 
     fn generate_init_fn(&mut self, init_fn: &TyFnDecl) -> Result<(), Diag> {
-        self.generate_fn_decl_head("__init__", init_fn)?;
+        // a generic node takes its dimensions as constructor arguments
+        let mut params = self.tenv.borrow().dim_params(&ModName::Named(self.name.to_owned()));
+        params.extend(fn_param_names(init_fn));
+        self.generate_fn_decl_head("__init__", &params)?;
         self.tab();
         self.indent()?;
         writeln!(self.buf, "super({}, self).__init__()", self.name)?;
@@ -317,14 +425,35 @@ impl Module {
                 .as_string();
             let core_cloned = self.core.clone();
             let core = core_cloned.borrow();
-            let op = core.find_mod(&module_name).unwrap();
-            write!(self.buf, "{}", op.gen_fn_app(&init.fn_name, init.fn_args.as_slice())?)?;
-            writeln!(self.buf, "")?;
+            let out = match core.find_mod(&module_name) {
+                Some(op) => {
+                    // including the arguments inferred from the input
+                    let fn_args = self.tenv.borrow()
+                        .resolve_init(&ModName::Named(self.name.to_owned()), &init.name)
+                        .unwrap_or_else(|| init.fn_args.clone());
+                    op.gen_fn_app(&init.fn_name, fn_args.as_slice())?
+                }
+                // user-defined node
+                None => {
+                    let mut args = vec![];
+                    for a in &init.dim_args {
+                        args.push(format!("{}={}", a.name.clone().unwrap(), a.arg.as_str().unwrap()));
+                    }
+                    for a in &init.fn_args {
+                        let val = self.gen_expr(&a.arg, "")?;
+                        args.push(format!("{}={}", a.name.clone().unwrap(), val));
+                    }
+                    format!("{}({})", module_name, args.join(", "))
+                }
+            };
+            match init.count {
+                Some(n) => writeln!(self.buf, "nn.ModuleList([{} for _ in range({})])", out, n)?,
+                None => writeln!(self.buf, "{}", out)?,
+            }
         }
 
         let fn_new = self.fns.borrow().get("new").unwrap().clone();
-        self.collect_term(&fn_new.func_block, None, true)?;
-        self.generate_fn()?;
+        self.gen_fn_body(&fn_new.func_block, "x", false)?;
 
         self.shift_tab();
         Ok(())
@@ -403,7 +532,7 @@ impl Generator {
         use self::TyDecl::*;
         match decl {
             TyUseStmt(stmt) => {
-                for name in stmt.imported_names.iter() {
+                for (name, _alias) in stmt.imported_names.iter() {
                     self.imports.insert(
                         (stmt.mod_name.to_owned(),
                          name.to_owned()
@@ -430,6 +559,65 @@ impl Generator {
 
 }
 
+/// `Attn::forward((q: [..], k: [..]) -> [..])` for multiple inputs
+fn fmt_ty_sig(ty: &Type) -> String {
+    match ty {
+        Type::FUN(m, n, box Type::FnArgs(args, _), r, _) => {
+            let params = args
+                .iter()
+                .map(|a| match a {
+                    Type::FnArg(Some(name), ty, _) => format!("{}: {:?}", name, ty),
+                    _ => format!("{:?}", a),
+                })
+                .collect::<Vec<_>>();
+            format!("{}::{}(({}) -> {:?})", m, n, params.join(", "), r)
+        }
+        _ => format!("{:?}", ty),
+    }
+}
+
+/// `out, (h, c)` on the left of an assignment
+fn gen_pattern(pat: &Pattern) -> String {
+    match pat {
+        Pattern::Ident(name) => name.to_owned(),
+        Pattern::Tuple(items) => format!("({})", items.iter().map(gen_pattern).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+fn strip_expr(term: &TyTerm) -> &TyTerm {
+    match term {
+        TyTerm::TyExpr(t, ..) => strip_expr(t),
+        _ => term,
+    }
+}
+
+fn is_literal(term: &TyTerm) -> bool {
+    use self::TyTerm::*;
+    match strip_expr(term) {
+        TyInteger(..) | TyFloat(..) | TyBool(..) => true,
+        TyTuple(_, ts, _) => ts.iter().all(is_literal),
+        _ => false,
+    }
+}
+
+/// terms that can be used as is without assigning to a variable first
+fn is_atomic(term: &TyTerm) -> bool {
+    use self::TyTerm::*;
+    match strip_expr(term) {
+        TyIdent(..) | TyFieldAccess(..) | TyNone => true,
+        TyTuple(_, ts, _) => ts.iter().all(is_atomic),
+        t => is_literal(t),
+    }
+}
+
+/// whether generating the term writes intermediate values to a variable
+fn has_nested_app(term: &TyTerm) -> bool {
+    match strip_expr(term) {
+        TyTerm::TyFnApp(fn_app) => fn_app.args.iter().any(|a| !is_atomic(&a.arg)),
+        _ => false,
+    }
+}
+
 impl From<::std::fmt::Error> for Diag {
     fn from(_error: ::std::fmt::Error) -> Diag {
         Diag::UnknownError
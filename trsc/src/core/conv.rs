@@ -1,239 +1,353 @@
-use core::{MethodName, Op, PyTorch, Resolve};
+use core::{float_dtype, inferred_arg, inits_span, MethodName, Op, PyTorch, Resolve};
 use errors::Diag;
 use span::CSpan;
 use typing::typed_term::{ArgsVecInto, TyFnAppArg, TyTerm};
 use typing::{Type, TypeEnv};
 
 use std::fmt::Write;
+use std::collections::BTreeMap;
+use parsing::term;
+use codespan::ByteSpan;
 
 use self::TyTerm::*;
 
 
-macro_rules! read_2_tuple {
-    ($var:expr) => {
-        if let box TyExpr(box TyTuple(_,vs,_),_,_) = $var { // TyExpr
-            (vs[0].clone().as_num()?, vs[1].clone().as_num()?)
-        } else {
-            panic!("{:#?}", $var);
-        }
-    };
-}
-
-macro_rules! read_from_init {
-    ($var:expr, $default:expr) => {
-        $var
-            .map(|t| (t, t.ty()) )
-            .and_then(|(t, ty)|
-                if let Type::Tuple(..) = ty {
-                    Some(read_2_tuple!(t))
+/// an `int` applies to every spatial dimension, a tuple has one value each
+pub fn read_spatial(term: Option<&Box<TyTerm>>, rank: usize, default: Vec<i64>) -> Option<Vec<i64>> {
+    match term {
+        None => Some(default),
+        Some(t) => match t.ty() {
+            Type::Tuple(..) => {
+                if let box TyExpr(box TyTuple(_, vs, _), _, _) = t {
+                    vs.iter().map(|v| v.as_num()).collect()
                 } else {
-                    let p0 = t.as_num()?;
-                    Some((p0, p0))
+                    panic!("{:#?}", t);
                 }
-            ).unwrap_or($default)
-    };
+            }
+            _ => t.as_num().map(|v| vec![v; rank]),
+        },
+    }
 }
 
-#[derive(Debug, Op)]
-#[path = "conv"]
-#[forward = "?() -> unit"]
-#[new = "?() -> unit"]
-#[stateful]
-pub struct Conv2d;
+/// output size of a convolution or pooling along one spatial dimension:
+/// `(input + 2 * padding - dilation * (kernel_size - 1) - 1) / stride + 1`
+/// where `input` may be symbolic. `ceil_mode` rounds the division up
+fn conv_output_dim(input: Type, k: i64, p: i64, d: i64, s: i64, ceil_mode: bool, span: ByteSpan) -> Type {
+    let input_num = input.as_num();
+    let round = if ceil_mode { s - 1 } else { 0 };
+    let offset = Type::ResolvedDim(2 * p - d * (k - 1) - 1 + round, span);
+    let numerator = Type::dim_op(term::Op::Add, input, offset, span);
+    let quotient = Type::dim_op(term::Op::Div, numerator, Type::ResolvedDim(s, span), span);
+    let out = Type::dim_op(term::Op::Add, quotient, Type::ResolvedDim(1, span), span);
+    // the last window has to start inside the input or its left padding
+    match (ceil_mode, input_num, out.as_num()) {
+        (true, Some(i), Some(o)) if (o - 1) * s >= i + p => Type::ResolvedDim(o - 1, span),
+        _ => out,
+    }
+}
 
-impl Resolve for Conv2d {
-    fn resolve( &self,
-        tenv: &mut TypeEnv,
-        fn_name: &str,
-        arg_ty: Type,
-        _ret_ty: Type,
-        _args: Vec<TyFnAppArg>,
-        inits: Option<Vec<TyFnAppArg>>
-    ) -> Option<Result<Type, Diag>> {
-        match fn_name {
-            "forward" => {
-                let forward_args = arg_ty.as_args_map()?;
-                let x_ty = &forward_args["x"];
-                if !x_ty.is_resolved() {
-                    None
-                } else {
-                    let init_map = inits?.to_btreemap()?;
-                    let (k0, k1) = read_from_init!(init_map.get("kernel_size"), (0, 0));
-                    let (p0, p1) = read_from_init!(init_map.get("padding"), (0, 0));
-                    let (d0, d1) = read_from_init!(init_map.get("dilation"), (1, 1));
-                    let (s0, s1) = read_from_init!(init_map.get("stride"), (1, 1));
-
-
-                    let in_ch = init_map.get("in_ch").map(|t|t.as_num().unwrap()).expect("does not have in_ch");
-                    let out_ch = init_map.get("out_ch").map(|t|t.as_num().unwrap()).expect("does not have in_ch");
-
-                    let dims = x_ty.as_vec()?;
-                    let (n, c_in, h_in, w_in) = (
-                        dims[0].to_owned(),
-                        dims[1].to_owned().as_num().unwrap(),
-                        dims[2].to_owned().as_num().unwrap(),
-                        dims[3].to_owned().as_num().unwrap()
-                    );
-
-                    assert_eq!(c_in, in_ch);
-                    // println!("BLAH: {:?}", x_ty);
-                    let h_out = (h_in + 2 * p0 - d0 * (k0 -1) - 1) / s0 + 1;
-                    let w_out = (w_in + 2 * p1 - d1 * (k1 -1) - 1) / s1 + 1;
-
-                    let span = x_ty.span();
-
-                    Some(Ok( // returns a function
-                        fun!(
-                            "Conv2d",
-                            "forward",
-                            arg_ty,
-                            Type::TSR(vec![
-                                n,
-                                Type::ResolvedDim(out_ch, span),
-                                Type::ResolvedDim(h_out, span),
-                                Type::ResolvedDim(w_out, span),
-                            ], span)
-                        )
-                    ))
+/// output size of a transposed convolution along one spatial dimension:
+/// `(input - 1) * stride - 2 * padding + dilation * (kernel_size - 1) + output_padding + 1`
+fn conv_transpose_output_dim(input: Type, k: i64, p: i64, d: i64, s: i64, op: i64, span: ByteSpan) -> Type {
+    let scaled = Type::dim_op(term::Op::Mult, input, Type::ResolvedDim(s, span), span);
+    let offset = Type::ResolvedDim(-s - 2 * p + d * (k - 1) + op + 1, span);
+    Type::dim_op(term::Op::Add, scaled, offset, span)
+}
+
+/// PyTorch only pads the output of a transposed convolution
+/// by less than the stride or the dilation
+fn check_output_padding(window: &Window, span: ByteSpan) -> Result<(), Diag> {
+    let params = window.output_padding.iter().zip(&window.stride).zip(&window.dilation);
+    match params.map(|((&op, &s), &d)| (op, s, d)).find(|&(op, s, d)| op >= s && op >= d) {
+        Some((op, s, d)) => Err(Diag::OutputPaddingTooLarge(op, s, d, span)),
+        None => Ok(()),
+    }
+}
+
+/// the sliding window of a convolution or pooling,
+/// one value per spatial dimension
+pub struct Window {
+    kernel_size: Vec<i64>,
+    stride: Vec<i64>,
+    padding: Vec<i64>,
+    dilation: Vec<i64>,
+    /// added to one side of the output of a transposed convolution
+    output_padding: Vec<i64>,
+    /// pooling keeps a partial window at the end
+    ceil_mode: bool,
+}
+
+impl Window {
+    /// pooling strides by the kernel size unless told otherwise,
+    /// and pads by at most half of it
+    pub fn from_args(map: &BTreeMap<String, Box<TyTerm>>, rank: usize, pooling: bool) -> Option<Result<Window, Diag>> {
+        // PyTorch rejects a window that does not move or pads by a negative amount
+        for &(param, min) in WINDOW_MINIMUMS {
+            if let Some(t) = map.get(param) {
+                if let Some(&v) = read_spatial(Some(t), rank, vec![])?.iter().find(|&&v| v < min) {
+                    return Some(Err(Diag::ArgTooSmall(param.to_owned(), v, min, t.span())));
                 }
-            },
-            "new" => {
-                Some(Ok(fun!(
-                    "Conv2d",
-                    "new",
-                    args!(
-                        arg!("in_ch", int!()),
-                        arg!("out_ch", int!()),
-                        arg!("kernel_size", tenv.fresh_var(CSpan::fresh_span()))
-                    ),
-                    module!("Conv2d")
-                )))
             }
-            _ => unimplemented!(),
         }
+        let kernel_size = read_spatial(map.get("kernel_size"), rank, vec![0; rank])?;
+        let default_stride = if pooling { kernel_size.clone() } else { vec![1; rank] };
+        let window = Window {
+            stride: read_spatial(map.get("stride"), rank, default_stride)?,
+            padding: read_spatial(map.get("padding"), rank, vec![0; rank])?,
+            dilation: read_spatial(map.get("dilation"), rank, vec![1; rank])?,
+            output_padding: read_spatial(map.get("output_padding"), rank, vec![0; rank])?,
+            ceil_mode: map.get("ceil_mode").and_then(|t| t.as_bool()).unwrap_or(false),
+            kernel_size,
+        };
+        if let Some(t) = map.get("output_padding") {
+            if let Err(e) = check_output_padding(&window, t.span()) {
+                return Some(Err(e));
+            }
+        }
+        if let (true, Some(t)) = (pooling, map.get("padding")) {
+            let mut pads = window.padding.iter().zip(&window.kernel_size);
+            if let Some((&p, &k)) = pads.find(|&(&p, &k)| p > k / 2) {
+                return Some(Err(Diag::PoolPaddingTooLarge(p, k, t.span())));
+            }
+        }
+        Some(Ok(window))
+    }
+
+    /// the spatial dimensions of the output given those of the input,
+    /// a resolved output has to be at least 1
+    pub fn output_dims(&self, inputs: &[Type], transposed: bool, span: ByteSpan) -> Result<Vec<Type>, Diag> {
+        inputs.iter().enumerate().map(|(i, input)| {
+            let (k, p, d, s) = (self.kernel_size[i], self.padding[i], self.dilation[i], self.stride[i]);
+            let out = if transposed {
+                conv_transpose_output_dim(input.clone(), k, p, d, s, self.output_padding[i], span)
+            } else {
+                conv_output_dim(input.clone(), k, p, d, s, self.ceil_mode, span)
+            };
+            match (input.as_num(), out.as_num()) {
+                (Some(i), Some(o)) if o < 1 => Err(Diag::NonPositiveOutput(i, o, span)),
+                _ => Ok(out),
+            }
+        }).collect()
     }
+}
 
+/// the smallest value each window parameter may take
+const WINDOW_MINIMUMS: &[(&str, i64)] = &[
+    ("kernel_size", 1),
+    ("stride", 1),
+    ("padding", 0),
+    ("dilation", 1),
+    ("output_padding", 0),
+];
+
+/// the input should be `[n, c, spatial..]` with `rank` spatial dimensions
+/// as set up by the arguments at `span`
+pub fn rank_mismatch(tenv: &mut TypeEnv, x_ty: &Type, rank: usize, span: ByteSpan) -> Diag {
+    let expected = (0..rank + 2).map(|_| tenv.fresh_dim(span)).collect();
+    let expected = Type::TSR(expected, box float_dtype(x_ty), span);
+    Diag::RankMismatch(x_ty.clone(), expected)
 }
 
-impl PyTorch for Conv2d {
-    
-    fn pytorch_name(&self) -> &'static str {
-        "nn.Conv2d"
+/// `[n, in_ch, spatial..]` to `[n, out_ch, spatial'..]` for a convolution
+/// over `rank` spatial dimensions
+fn resolve_conv(
+    op: &str,
+    rank: usize,
+    transposed: bool,
+    tenv: &mut TypeEnv,
+    arg_ty: Type,
+    inits: Option<Vec<TyFnAppArg>>,
+) -> Option<Result<Type, Diag>> {
+    let forward_args = arg_ty.as_args_map()?;
+    let x_ty = &forward_args["x"];
+    if !x_ty.is_resolved() {
+        return None;
     }
+    let inits = inits?;
+    let init_span = inits_span(&inits, x_ty.span());
+    let init_map = inits.to_btreemap()?;
+    let window = match Window::from_args(&init_map, rank, false)? {
+        Ok(window) => window,
+        Err(e) => return Some(Err(e)),
+    };
 
-    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
-        let mut buf = String::new();
-        match name {
-            "new" => {
-                write!(buf, "{}(", self.pytorch_name()).unwrap();
-                let map = args.to_btreemap().unwrap();
-                write!(buf, "in_channels={}, ", map["in_ch"].as_str().unwrap()).unwrap();
-                write!(buf, "out_channels={}, ", map["out_ch"].as_str().unwrap()).unwrap();
-                write!(buf, "kernel_size={})", map["kernel_size"].as_str().unwrap()).unwrap();
-                Ok(buf)
-            }
-            "forward" => {
-                let args: Vec<_> = args.iter().map(|i| i.name.clone().unwrap()).collect();
-                write!(buf, "{}", args.join(", ")).unwrap();
-                Ok(buf)
+    let (out_ch, out_ch_span) = match init_map.get("out_ch") {
+        Some(t) => (t.as_num()?, t.span()),
+        None => return Some(Err(Diag::MissingInitArg(op.to_owned(), "out_ch".to_owned(), init_span))),
+    };
+
+    let dims = x_ty.as_vec()?;
+    if dims.len() != rank + 2 {
+        return Some(Err(rank_mismatch(tenv, x_ty, rank, init_span)));
+    }
+    let (n, c_in) = (dims[0].to_owned(), dims[1].to_owned());
+
+    // without `in_ch` the channels of the input are taken as is
+    let (in_ch, in_ch_span) = match init_map.get("in_ch") {
+        Some(t) => (t.as_num()?, t.span()),
+        None => (c_in.as_num()?, c_in.span()),
+    };
+
+    if let Some(c) = c_in.as_num() {
+        if c != in_ch {
+            let in_ch = Type::ResolvedDim(in_ch, in_ch_span);
+            return Some(Err(Diag::ChannelMismatch(in_ch, c_in, arg_ty.span())));
+        }
+    }
+
+    // channels are split into `groups` independent convolutions
+    if let Some(t) = init_map.get("groups") {
+        let groups = t.as_num()?;
+        for &(param, channels) in &[("in_ch", in_ch), ("out_ch", out_ch)] {
+            if groups < 1 || channels % groups != 0 {
+                return Some(Err(Diag::IndivisibleGroups(param.to_owned(), channels, groups, t.span())));
             }
-            _ => panic!("{} is not implemented", name),
         }
     }
 
-}
+    let span = x_ty.span();
+    let dtype = float_dtype(x_ty);
+    let spatial = &dims[2..];
 
-#[allow(non_camel_case_types)]
-#[derive(Debug, Op)]
-#[path = "conv"]
-#[forward = "?() -> unit"]
-pub struct maxpool2d;
-
-
-impl Resolve for maxpool2d {
-    fn resolve(
-        &self,
-        _tenv: &mut TypeEnv,
-        fn_name: &str,
-        arg_ty: Type,
-        _ret_ty: Type,
-        args: Vec<TyFnAppArg>,
-        _inits: Option<Vec<TyFnAppArg>>,
-    ) -> Option<Result<Type, Diag>> {
-        match fn_name {
-            "forward" => {
-                let args_ty_map = arg_ty.as_args_map()?;
-                let x_ty = args_ty_map.get("x").expect("No x argument");
-                let args_map = args.to_btreemap()?;
-
-                if !x_ty.is_resolved() {
-                    None
-                } else {
-                    let (k0, k1) = read_from_init!(args_map.get("kernel_size"), (0, 0));
-                    let (p0, p1) = read_from_init!(args_map.get("padding"), (0, 0));
-                    let (d0, d1) = read_from_init!(args_map.get("dilation"), (1, 1));
-                    let (s0, s1) = read_from_init!(args_map.get("stride"), (k0, k1));
-
-                    let dims = x_ty.as_vec()?;
-                    let (n, c_in, h_in, w_in) = (
-                        dims[0].to_owned(),
-                        dims[1].to_owned(),
-                        dims[2].to_owned().as_num().unwrap(),
-                        dims[3].to_owned().as_num().unwrap()
-                    );
-                    // println!("BLAH: {:?}", x_ty);
-                    let h_out = (h_in + 2 * p0 - d0 * (k0 -1) - 1) / s0 + 1;
-                    let w_out = (w_in + 2 * p1 - d1 * (k1 -1) - 1) / s1 + 1;
-
-                    let span = x_ty.span();
-
-                    Some(Ok( // returns a function
-                        fun!(
-                            "maxpool2d",
-                            "forward",
-                            arg_ty,
-                            Type::TSR(vec![
-                                n,
-                                c_in.clone(),
-                                Type::ResolvedDim(h_out, span),
-                                Type::ResolvedDim(w_out, span),
-                            ], span)
-                        )
-                    ))
-                }
-            },
-            _ => None,
-        }
+    // a symbolic input channel is solved to `in_ch`
+    let mut x_dims = vec![n.clone(), Type::ResolvedDim(in_ch, in_ch_span)];
+    x_dims.extend(spatial.iter().cloned());
+    let mut out_dims = vec![n, Type::ResolvedDim(out_ch, out_ch_span)];
+    match window.output_dims(spatial, transposed, span) {
+        Ok(spatial) => out_dims.extend(spatial),
+        Err(e) => return Some(Err(e)),
     }
 
+    Some(Ok( // returns a function
+        fun!(
+            op,
+            "forward",
+            args!(arg!("x", Type::TSR(x_dims, box dtype.clone(), span))),
+            Type::TSR(out_dims, box dtype, span)
+        )
+    ))
 }
 
-impl PyTorch for maxpool2d {
+/// `in_ch` is the channel dimension of the input
+fn infer_in_ch(fn_ty: &Type, inits: &[TyFnAppArg]) -> Vec<TyFnAppArg> {
+    if inits.iter().any(|a| a.name == Some("in_ch".to_owned())) {
+        return vec![];
+    }
+    fn_ty.first_arg_ty()
+        .and_then(|x_ty| x_ty.as_vec())
+        .and_then(|dims| inferred_arg("in_ch", dims.get(1)?))
+        .into_iter()
+        .collect()
+}
 
-    fn pytorch_name(&self) -> &'static str {
-        "F.max_pool2d"
+/// the constructor of a convolution layer, optional parameters
+/// are listed in PyTorch's order and keep its defaults unless given
+fn gen_conv_new(pytorch_name: &str, args: &[TyFnAppArg], optional: &[&str]) -> String {
+    let mut buf = String::new();
+    write!(buf, "{}(", pytorch_name).unwrap();
+    let map = args.to_btreemap().unwrap();
+    write!(buf, "in_channels={}, ", map["in_ch"].as_str().unwrap()).unwrap();
+    write!(buf, "out_channels={}, ", map["out_ch"].as_str().unwrap()).unwrap();
+    write!(buf, "kernel_size={}", map["kernel_size"].as_str().unwrap()).unwrap();
+    for param in optional {
+        if let Some(t) = map.get(*param) {
+            write!(buf, ", {}={}", param, t.as_str().unwrap()).unwrap();
+        }
     }
+    write!(buf, ")").unwrap();
+    buf
+}
+
+const CONV_PARAMS: &[&str] = &["stride", "padding", "dilation", "groups", "bias"];
+const CONV_TRANSPOSE_PARAMS: &[&str] = &["stride", "padding", "output_padding", "groups", "bias", "dilation"];
 
-    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
-        let mut buf = String::new();
-        match name {
-            "new" => {
-                write!(buf, "{}(", self.pytorch_name()).unwrap();
-                let map = args.to_btreemap().unwrap();
-                write!(buf, "in_channels={}, ", map["in_ch"].as_str().unwrap()).unwrap();
-                write!(buf, "out_channels={}, ", map["out_ch"].as_str().unwrap()).unwrap();
-                write!(buf, "kernel_size={})", map["kernel_size"].as_str().unwrap()).unwrap();
-                Ok(buf)
+/// `Resolve` and `PyTorch` for a convolution over `$rank` spatial dimensions
+macro_rules! impl_conv {
+    ($op:ident, $pytorch_name:expr, $rank:expr, $transposed:expr, $params:expr) => {
+        impl Resolve for $op {
+            fn resolve(
+                &self,
+                tenv: &mut TypeEnv,
+                fn_name: &str,
+                arg_ty: Type,
+                _ret_ty: Type,
+                _args: Vec<TyFnAppArg>,
+                inits: Option<Vec<TyFnAppArg>>,
+            ) -> Option<Result<Type, Diag>> {
+                match fn_name {
+                    "forward" => resolve_conv(self.get_name(), $rank, $transposed, tenv, arg_ty, inits),
+                    _ => unimplemented!(),
+                }
             }
-            "forward" => {
-                let args: Vec<_> = args
-                    .iter()
-                    .map(|i| i.name.clone().unwrap())
-                    .collect();
-                write!(buf, "{}", args.join(", ")).unwrap();
-                Ok(buf)
+
+            fn infer_inits(&self, fn_ty: &Type, inits: &[TyFnAppArg]) -> Vec<TyFnAppArg> {
+                infer_in_ch(fn_ty, inits)
             }
-            _ => panic!("{} is not implemented", name),
         }
-    }
-}
\ No newline at end of file
+
+        impl PyTorch for $op {
+            fn pytorch_name(&self) -> &'static str {
+                $pytorch_name
+            }
+
+            fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
+                match name {
+                    "new" => Ok(gen_conv_new(self.pytorch_name(), args, $params)),
+                    "forward" => {
+                        let args: Vec<_> = args.iter().map(|i| i.name.clone().unwrap()).collect();
+                        Ok(args.join(", "))
+                    }
+                    _ => panic!("{} is not implemented", name),
+                }
+            }
+        }
+    };
+}
+
+#[derive(Debug, Op)]
+#[path = "conv"]
+#[forward = "?() -> unit"]
+#[new = "(in_ch: int = ?, out_ch: int, kernel_size: int, stride: int = 1, padding: int = 0, dilation: int = 1, groups: int = 1, bias: bool = true) -> self"]
+#[stateful]
+pub struct Conv1d;
+impl_conv!(Conv1d, "nn.Conv1d", 1, false, CONV_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "conv"]
+#[forward = "?() -> unit"]
+#[new = "(in_ch: int = ?, out_ch: int, kernel_size: int2, stride: int2 = 1, padding: int2 = 0, dilation: int2 = 1, groups: int = 1, bias: bool = true) -> self"]
+#[stateful]
+pub struct Conv2d;
+impl_conv!(Conv2d, "nn.Conv2d", 2, false, CONV_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "conv"]
+#[forward = "?() -> unit"]
+#[new = "(in_ch: int = ?, out_ch: int, kernel_size: int3, stride: int3 = 1, padding: int3 = 0, dilation: int3 = 1, groups: int = 1, bias: bool = true) -> self"]
+#[stateful]
+pub struct Conv3d;
+impl_conv!(Conv3d, "nn.Conv3d", 3, false, CONV_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "conv"]
+#[forward = "?() -> unit"]
+#[new = "(in_ch: int = ?, out_ch: int, kernel_size: int, stride: int = 1, padding: int = 0, output_padding: int = 0, groups: int = 1, bias: bool = true, dilation: int = 1) -> self"]
+#[stateful]
+pub struct ConvTranspose1d;
+impl_conv!(ConvTranspose1d, "nn.ConvTranspose1d", 1, true, CONV_TRANSPOSE_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "conv"]
+#[forward = "?() -> unit"]
+#[new = "(in_ch: int = ?, out_ch: int, kernel_size: int2, stride: int2 = 1, padding: int2 = 0, output_padding: int2 = 0, groups: int = 1, bias: bool = true, dilation: int2 = 1) -> self"]
+#[stateful]
+pub struct ConvTranspose2d;
+impl_conv!(ConvTranspose2d, "nn.ConvTranspose2d", 2, true, CONV_TRANSPOSE_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "conv"]
+#[forward = "?() -> unit"]
+#[new = "(in_ch: int = ?, out_ch: int, kernel_size: int3, stride: int3 = 1, padding: int3 = 0, output_padding: int3 = 0, groups: int = 1, bias: bool = true, dilation: int3 = 1) -> self"]
+#[stateful]
+pub struct ConvTranspose3d;
+impl_conv!(ConvTranspose3d, "nn.ConvTranspose3d", 3, true, CONV_TRANSPOSE_PARAMS);
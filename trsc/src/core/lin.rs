@@ -1,81 +1,60 @@
-use core::{MethodName, Op, PyTorch, Resolve};
+use core::{float_dtype, inferred_arg, init_dim, inits_span, unwrap_dim, MethodName, Op, PyTorch, Resolve};
 use errors::Diag;
 use span::CSpan;
-use typing::typed_term::{ArgsVecInto, TyFnAppArg, TyTerm};
+use typing::typed_term::{ArgsVecInto, TyFnAppArg};
 use typing::{Type, TypeEnv};
 use std::fmt::Write;
 
 #[derive(Debug, Op)]
 #[path = "lin"]
-#[new = "(in: int, out: int) -> self"]
+#[new = "(in: int = ?, out: int) -> self"]
 #[forward = "?(x: tsr0) -> tsr0"]
 #[init_normal = "(std: float) -> unit"]
 #[stateful]
 pub struct Linear;
 
 impl Resolve for Linear {
-    /// output same shape as input
+    /// applies to the last dimension, leading dimensions are unchanged
     fn resolve(
         &self,
-        _tenv: &mut TypeEnv,
+        tenv: &mut TypeEnv,
         fn_name: &str,
         arg_ty: Type,
-        ret_ty: Type,
+        _ret_ty: Type,
         _args: Vec<TyFnAppArg>,
         inits: Option<Vec<TyFnAppArg>>, // ... refactor into span error
     ) -> Option<Result<Type, Diag>> {
         match fn_name {
             "forward" => {
                 if inits.is_some() {
-                    let hm = inits.unwrap().to_btreemap().unwrap();
-                    if !hm.contains_key("in") {
-                        panic!("Initatialize Linear with parameter in=");
-                    } else if !hm.contains_key("out") {
-                        panic!("Initatialize Linear with parameter out=");
-                    }
-
-                    let in_dim = hm.get("in").and_then(|t| unwrap_dim(t))?;
-                    let out_dim = hm.get("out").and_then(|t| unwrap_dim(t))?;
-
-                    let span = arg_ty.span();
-
-                    let (a, b) = match (arg_ty.first_arg_ty()?.as_vec(), ret_ty.as_vec()) {
-                        (None, None) => return None,
-                        (Some(ref mut a), None) |
-                        (None, Some(ref mut a)) => {
-                            // modify the last dimension
-                            let mut b = a.clone();
-                            {
-                                let mut last_arg_dim = a.last_mut().unwrap();
-                                let mut last_ret_dim = b.last_mut().unwrap();
-                                *last_arg_dim = Type::ResolvedDim(in_dim, CSpan::fresh_span());
-                                *last_ret_dim = Type::ResolvedDim(out_dim, CSpan::fresh_span());
-                            };
-
-                            (a.clone(), b)
-                        }
-                        (Some(ref mut a), Some(ref mut b)) => {
-                            if a.len() != b.len() {
-                                // return dimension mismatch
-                                return Some(Err(Diag::TypeError(arg_ty, ret_ty)));
-                            }
-                            // modify the last dimension
-                            {
-                                let mut last_arg_dim = a.last_mut().unwrap();
-                                let mut last_ret_dim = b.last_mut().unwrap();
-                                *last_arg_dim = Type::ResolvedDim(in_dim, CSpan::fresh_span());
-                                *last_ret_dim = Type::ResolvedDim(out_dim, CSpan::fresh_span());
-                            };
-
-                            (a.clone(), b.clone())
-                        }
+                    let inits = inits.unwrap();
+                    let span = inits_span(&inits, arg_ty.span());
+                    let hm = inits.to_btreemap().unwrap();
+                    let x_ty = arg_ty.as_args_map()?.get("x")?.clone();
+                    let in_dim = match hm.get("in") {
+                        Some(t) => match unwrap_dim(t)? {
+                            Ok(dim) => dim,
+                            Err(e) => return Some(Err(e)),
+                        },
+                        // wait until the last dimension of the input is known
+                        None => match x_ty.as_vec()?.last()? {
+                            dim @ Type::ResolvedDim(..) => dim.clone(),
+                            _ => return None,
+                        },
+                    };
+                    let out_dim = match init_dim(self.get_name(), &hm, "out", span)? {
+                        Ok(dim) => dim,
+                        Err(e) => return Some(Err(e)),
                     };
 
+                    // `[..., in] -> [..., out]`
+                    let leading = tenv.fresh_dims(CSpan::fresh_span());
+                    let dtype = float_dtype(&x_ty);
                     Some(Ok(fun!(
                         self.get_name(),
                         "forward",
-                        args!(arg!("x",Type::TSR(a, span))),
-                        Type::TSR(b, span)
+                        args!(arg!("x", tsr!(vec![leading.clone(), in_dim], dtype.clone()))),
+                        tsr!(vec![leading, out_dim], dtype)
                     )))
                 } else {
                     None
@@ -85,6 +64,18 @@ impl Resolve for Linear {
         }
     }
 
+    /// `in` is the last dimension of the input
+    fn infer_inits(&self, fn_ty: &Type, inits: &[TyFnAppArg]) -> Vec<TyFnAppArg> {
+        if inits.iter().any(|a| a.name == Some("in".to_owned())) {
+            return vec![];
+        }
+        fn_ty.first_arg_ty()
+            .and_then(|x_ty| x_ty.as_vec())
+            .and_then(|dims| inferred_arg("in", dims.last()?))
+            .into_iter()
+            .collect()
+    }
+
 }
 impl PyTorch for Linear {
 
@@ -98,8 +89,8 @@ impl PyTorch for Linear {
             "new" => {
                 let map = args.to_btreemap().unwrap();
                 write!(buf, "{}(", self.pytorch_name()).unwrap();
-                write!(buf, "in_features={:?}, ", map["in"].as_num().unwrap()).unwrap();
-                write!(buf, "out_features={:?})", map["out"].as_num().unwrap()).unwrap();
+                write!(buf, "in_features={}, ", map["in"].as_str().unwrap()).unwrap();
+                write!(buf, "out_features={})", map["out"].as_str().unwrap()).unwrap();
                 Ok(buf)
             }
             "forward" => {
@@ -118,11 +109,3 @@ impl PyTorch for Linear {
         }
     }
 }
-
-fn unwrap_dim(in_dim: &TyTerm) -> Option<i64> {
-    match in_dim.ty() {
-        Type::INT(_) => in_dim.as_num(),
-        Type::ResolvedDim(num, _) => Some(num),
-        _ => panic!("{:?} is not a numeric value!", in_dim),
-    }
-}
@@ -0,0 +1,60 @@
+use core::{MethodName, Op, PyTorch, Resolve};
+use std::fmt::Write;
+use span::CSpan;
+use typing::typed_term::TyFnAppArg;
+use typing::{Type, TypeEnv};
+use errors::Diag;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "loss"]
+#[forward = "?()"]
+pub struct cross_entropy;
+
+impl Resolve for cross_entropy {
+    /// class scores `[N, C]: f32` and class indices `[N]: i64` to a scalar
+    fn resolve(
+        &self,
+        tenv: &mut TypeEnv,
+        fn_name: &str,
+        _arg_ty: Type,
+        _ret_ty: Type,
+        _args: Vec<TyFnAppArg>,
+        _inits: Option<Vec<TyFnAppArg>>,
+    ) -> Option<Result<Type, Diag>> {
+        match fn_name {
+            "forward" => {
+                let span = CSpan::fresh_span();
+                let n = tenv.fresh_dim(span);
+                let c = tenv.fresh_dim(span);
+                Some(Ok(fun!(
+                    self.get_name(),
+                    "forward",
+                    args!(
+                        arg!("x", tsr!(vec![n.clone(), c], dtype!(F32))),
+                        arg!("target", tsr!(vec![n], dtype!(I64)))
+                    ),
+                    tsr!(vec![], dtype!(F32))
+                )))
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl PyTorch for cross_entropy {
+    fn pytorch_name(&self) -> &'static str {
+        "F.cross_entropy"
+    }
+    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
+        let mut buf = String::new();
+        match name {
+            "forward" => {
+                let args: Vec<_> = args.iter().map(|i| i.name.clone().unwrap()).collect();
+                write!(buf, "{}", args.join(", ")).unwrap();
+                Ok(buf)
+            }
+            _ => panic!("{} is not implemented", name),
+        }
+    }
+}
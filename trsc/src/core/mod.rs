@@ -1,14 +1,20 @@
-use typing::typed_term::TyFnAppArg;
+use typing::typed_term::{TyFnAppArg, TyTerm};
 use errors::Diag;
 use typing::{Type, TypeEnv};
-use std::collections::HashMap;
+use parsing::term::DType;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
+use codespan::ByteSpan;
 
 mod prelude;
 mod conv;
+mod pool;
 mod lin;
 mod reg;
+mod loss;
 mod nonlin;
+mod rnn;
+mod sparse;
 
 pub trait Op: PyTorch + Resolve {
     fn get_name(&self) -> &'static str;
@@ -16,6 +22,9 @@ pub trait Op: PyTorch + Resolve {
     fn ty_sigs(&self, tenv: &mut TypeEnv) -> Vec<(MethodName, Type)>;
 
     fn is_stateful(&self) -> bool;
+
+    /// optional parameters of a method and their default values
+    fn defaults(&self, fn_name: &str) -> Vec<(&'static str, &'static str)>;
 }
 
 pub trait Resolve {
@@ -30,6 +39,12 @@ pub trait Resolve {
     ) -> Option<Result<Type, Diag>> {
         panic!("{} is not yet implemented", fn_name);
     }
+
+    /// initialization arguments left out of the weights block that
+    /// can be read off the signature returned by `resolve`
+    fn infer_inits(&self, _fn_ty: &Type, _inits: &[TyFnAppArg]) -> Vec<TyFnAppArg> {
+        vec![]
+    }
 }
 
 pub trait PyTorch: Debug {
@@ -47,12 +62,85 @@ pub struct Core {
 
 pub type MethodName = &'static str;
 
+/// element type of the input to a layer with weights: `f16` stays as is,
+/// anything else must be `f32` so integer tensors have to be cast first
+pub fn float_dtype(x_ty: &Type) -> Type {
+    match x_ty.dtype() {
+        Some(dt @ Type::DTYPE(DType::F16, _)) => dt,
+        _ => Type::DTYPE(DType::F32, x_ty.span()),
+    }
+}
+
+/// the initialization arguments of a layer, `default` if there are none
+pub fn inits_span(inits: &[TyFnAppArg], default: ByteSpan) -> ByteSpan {
+    match (inits.first(), inits.last()) {
+        (Some(first), Some(last)) => first.span.to(last.span),
+        _ => default,
+    }
+}
+
+/// an initialization argument `name` taken from a resolved dimension,
+/// pointing at where the dimension came from
+pub fn inferred_arg(name: &str, dim: &Type) -> Option<TyFnAppArg> {
+    let num = dim.as_num()?;
+    let span = dim.span();
+    Some(TyFnAppArg {
+        name: Some(name.to_owned()),
+        arg: box TyTerm::TyInteger(Type::INT(span), num, span),
+        span,
+    })
+}
+
+/// the dimension given by the initialization argument `name`
+pub fn init_dim(op: &str, hm: &BTreeMap<String, Box<TyTerm>>, name: &str, span: ByteSpan) -> Option<Result<Type, Diag>> {
+    match hm.get(name) {
+        Some(t) => unwrap_dim(t),
+        None => Some(Err(Diag::MissingInitArg(op.to_owned(), name.to_owned(), span))),
+    }
+}
+
+/// a dimension parameter of a generic node stays symbolic.
+/// The dimension points at the argument in the weights block
+pub fn unwrap_dim(in_dim: &TyTerm) -> Option<Result<Type, Diag>> {
+    let span = in_dim.span();
+    match in_dim.ty() {
+        Type::INT(_) => in_dim.as_num().map(|n| Ok(Type::ResolvedDim(n, span))),
+        Type::ResolvedDim(num, _) => Some(Ok(Type::ResolvedDim(num, span))),
+        dim @ Type::DIM(..) => Some(Ok(dim.with_span(&span))),
+        ty => Some(Err(Diag::TypeError(ty, Type::INT(span)))),
+    }
+}
+
 impl Core {
     pub fn new() -> Self {
         let maps = hashmap! {
             "conv" => hashmap! {
+                "Conv1d" => box self::conv::Conv1d as Box<Op>,
                 "Conv2d" => box self::conv::Conv2d as Box<Op>,
-                "maxpool2d" => box self::conv::maxpool2d as Box<Op>,
+                "Conv3d" => box self::conv::Conv3d as Box<Op>,
+                "ConvTranspose1d" => box self::conv::ConvTranspose1d as Box<Op>,
+                "ConvTranspose2d" => box self::conv::ConvTranspose2d as Box<Op>,
+                "ConvTranspose3d" => box self::conv::ConvTranspose3d as Box<Op>,
+                // alias of `pool::maxpool2d`
+                "maxpool2d" => box self::pool::maxpool2d as Box<Op>,
+            },
+            "pool" => hashmap! {
+                "maxpool1d" => box self::pool::maxpool1d as Box<Op>,
+                "maxpool2d" => box self::pool::maxpool2d as Box<Op>,
+                "maxpool3d" => box self::pool::maxpool3d as Box<Op>,
+                "avgpool1d" => box self::pool::avgpool1d as Box<Op>,
+                "avgpool2d" => box self::pool::avgpool2d as Box<Op>,
+                "avgpool3d" => box self::pool::avgpool3d as Box<Op>,
+                "adaptive_avgpool2d" => box self::pool::adaptive_avgpool2d as Box<Op>,
+                "global_avgpool2d" => box self::pool::global_avgpool2d as Box<Op>,
+                "global_maxpool2d" => box self::pool::global_maxpool2d as Box<Op>,
+                "MaxPool1d" => box self::pool::MaxPool1d as Box<Op>,
+                "MaxPool2d" => box self::pool::MaxPool2d as Box<Op>,
+                "MaxPool3d" => box self::pool::MaxPool3d as Box<Op>,
+                "AvgPool1d" => box self::pool::AvgPool1d as Box<Op>,
+                "AvgPool2d" => box self::pool::AvgPool2d as Box<Op>,
+                "AvgPool3d" => box self::pool::AvgPool3d as Box<Op>,
+                "AdaptiveAvgPool2d" => box self::pool::AdaptiveAvgPool2d as Box<Op>,
             },
             "nonlin" => hashmap! {
                 "relu" => box self::nonlin::relu as Box<Op>,
@@ -61,15 +149,28 @@ impl Core {
                 "log_softmax" => box self::nonlin::log_softmax as Box<Op>,
                 "sigmoid" => box self::nonlin::sigmoid as Box<Op>,
             },
+            "loss" => hashmap! {
+                "cross_entropy" => box self::loss::cross_entropy as Box<Op>,
+            },
             "lin" => hashmap! {
                 "Linear" => box self::lin::Linear as Box<Op>,
             },
             "prelude" => hashmap! {
                 "view" => box self::prelude::view as Box<Op>,
+                "to" => box self::prelude::to as Box<Op>,
+            },
+            "rnn" => hashmap! {
+                "LSTM" => box self::rnn::LSTM as Box<Op>,
+                "GRU" => box self::rnn::GRU as Box<Op>,
+                "RNN" => box self::rnn::RNN as Box<Op>,
+            },
+            "sparse" => hashmap! {
+                "Embedding" => box self::sparse::Embedding as Box<Op>,
             },
             "reg" => hashmap! {
                 "Dropout2d" => box self::reg::Dropout2d as Box<Op>,
                 "BatchNorm1d" => box self::reg::BatchNorm1d as Box<Op>,
+                "LayerNorm" => box self::reg::LayerNorm as Box<Op>,
             }
         };
         Self {
@@ -81,6 +182,10 @@ impl Core {
         Some(op.ty_sigs(tenv))
     }
 
+    pub fn has_path(&self, path_name: &str) -> bool {
+        self.maps.contains_key(path_name)
+    }
+
     pub fn find(&self, path_name: &str, mod_name: &str) -> Option<&Box<Op>> {
         let ret = self.maps.get(path_name)?.get(mod_name)?;
         Some(ret)
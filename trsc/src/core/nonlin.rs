@@ -24,7 +24,7 @@ impl Resolve for sigmoid {
     ) -> Option<Result<Type, Diag>> {
         match fn_name {
             "forward" => {
-                let ty = tenv.fresh_var(CSpan::fresh_span());
+                let ty = tenv.fresh_tsr(CSpan::fresh_span());
                 Some(Ok(fun!(self.get_name(), "forward", args!(arg!("x", ty.clone())), ty)))
             }
             _ => unimplemented!(),
@@ -40,7 +40,7 @@ impl PyTorch for sigmoid {
         let mut buf = String::new();
         match name {
             "forward" => {
-                write!(buf, "x").unwrap();
+                write!(buf, "{}", args[0].name.clone().unwrap()).unwrap();
                 Ok(buf)
             }
             _ => panic!("{} is not implemented", name),
@@ -66,7 +66,7 @@ impl Resolve for tanh {
     ) -> Option<Result<Type, Diag>> {
         match fn_name {
             "forward" => {
-                let ty = tenv.fresh_var(CSpan::fresh_span());
+                let ty = tenv.fresh_tsr(CSpan::fresh_span());
                 Some(Ok(fun!(self.get_name(), "forward", args!(arg!("x", ty.clone())), ty)))
             }
             _ => unimplemented!(),
@@ -78,11 +78,11 @@ impl PyTorch for tanh {
     fn pytorch_name(&self) -> &'static str {
         "F.tanh"
     }
-    fn gen_fn_app(&self, name: &str, _args: &[TyFnAppArg]) -> Result<String, Diag> {
+    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
         let mut buf = String::new();
         match name {
             "forward" => {
-                write!(buf, "x").unwrap();
+                write!(buf, "{}", args[0].name.clone().unwrap()).unwrap();
                 Ok(buf)
             }
             _ => panic!("{} is not implemented", name),
@@ -108,7 +108,7 @@ impl Resolve for relu {
     ) -> Option<Result<Type, Diag>> {
         match fn_name {
             "forward" => {
-                let ty = tenv.fresh_var(CSpan::fresh_span());
+                let ty = tenv.fresh_tsr(CSpan::fresh_span());
                 Some(Ok(fun!(self.get_name(), "forward", args!(arg!("x", ty.clone())), ty)))
             }
             _ => unimplemented!(),
@@ -120,11 +120,11 @@ impl PyTorch for relu {
     fn pytorch_name(&self) -> &'static str {
         "F.relu"
     }
-    fn gen_fn_app(&self, name: &str, _args: &[TyFnAppArg]) -> Result<String, Diag> {
+    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
         let mut buf = String::new();
         match name {
             "forward" => {
-                write!(buf, "x").unwrap();
+                write!(buf, "{}", args[0].name.clone().unwrap()).unwrap();
                 Ok(buf)
             }
             _ => panic!("{} is not implemented", name),
@@ -150,7 +150,7 @@ impl Resolve for leaky_relu {
     ) -> Option<Result<Type, Diag>> {
         match fn_name {
             "forward" => {
-                let ty = tenv.fresh_var(CSpan::fresh_span());
+                let ty = tenv.fresh_tsr(CSpan::fresh_span());
                 Some(Ok(fun!(self.get_name(), "forward", args!(arg!("x", ty.clone())), ty)))
             }
             _ => unimplemented!(),
@@ -162,11 +162,11 @@ impl PyTorch for leaky_relu {
     fn pytorch_name(&self) -> &'static str {
         "F.leaky_relu"
     }
-    fn gen_fn_app(&self, name: &str, _args: &[TyFnAppArg]) -> Result<String, Diag> {
+    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
         let mut buf = String::new();
         match name {
             "forward" => {
-                write!(buf, "x").unwrap();
+                write!(buf, "{}", args[0].name.clone().unwrap()).unwrap();
                 Ok(buf)
             }
             _ => panic!("{} is not implemented", name),
@@ -0,0 +1,295 @@
+use core::{inits_span, MethodName, Op, PyTorch, Resolve};
+use core::conv::{rank_mismatch, read_spatial, Window};
+use errors::Diag;
+use span::CSpan;
+use typing::typed_term::{ArgsVecInto, TyFnAppArg};
+use typing::{Type, TypeEnv};
+
+use std::fmt::Write;
+
+/// how the spatial dimensions of the output are found
+#[derive(Clone, Copy)]
+enum Pooling {
+    /// a sliding window like a convolution with `in_ch == out_ch`
+    Window,
+    /// `output_size` is given
+    Adaptive,
+    /// every spatial dimension is pooled down to 1
+    Global,
+}
+
+/// `[n, c, spatial..]` to `[n, c, spatial'..]` over `rank` spatial dimensions.
+/// `params` are the call arguments of the functional form or the
+/// initialization arguments of the module form. `count_include_pad`
+/// only changes the averaged values, not the shape
+fn resolve_pool(
+    op: &str,
+    rank: usize,
+    pooling: Pooling,
+    tenv: &mut TypeEnv,
+    arg_ty: Type,
+    params: &[TyFnAppArg],
+) -> Option<Result<Type, Diag>> {
+    let x_ty = arg_ty.as_args_map()?.get("x")?.clone();
+    if !x_ty.is_resolved() {
+        return None;
+    }
+    let span = x_ty.span();
+    let params_span = inits_span(params, span);
+    let map = params.to_btreemap()?;
+
+    let dims = x_ty.as_vec()?;
+    if dims.len() != rank + 2 {
+        return Some(Err(rank_mismatch(tenv, &x_ty, rank, params_span)));
+    }
+
+    let spatial = match pooling {
+        Pooling::Window => {
+            if !map.contains_key("kernel_size") {
+                return Some(Err(Diag::MissingInitArg(op.to_owned(), "kernel_size".to_owned(), params_span)));
+            }
+            let window = match Window::from_args(&map, rank, true)? {
+                Ok(window) => window,
+                Err(e) => return Some(Err(e)),
+            };
+            match window.output_dims(&dims[2..], false, span) {
+                Ok(spatial) => spatial,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Pooling::Adaptive => match map.get("output_size") {
+            Some(t) => read_spatial(Some(t), rank, vec![])?
+                .into_iter()
+                .map(|size| Type::ResolvedDim(size, t.span()))
+                .collect(),
+            None => return Some(Err(Diag::MissingInitArg(op.to_owned(), "output_size".to_owned(), params_span))),
+        },
+        Pooling::Global => vec![Type::ResolvedDim(1, span); rank],
+    };
+
+    let mut out_dims = dims[..2].to_vec();
+    out_dims.extend(spatial);
+    Some(Ok( // returns a function
+        fun!(
+            op,
+            "forward",
+            arg_ty,
+            Type::TSR(out_dims, box x_ty.dtype()?, span)
+        )
+    ))
+}
+
+/// keyword arguments for the parameters that were given, in PyTorch's order
+fn gen_pool_kwargs(args: &[TyFnAppArg], params: &[&str]) -> Vec<String> {
+    let map = args.to_btreemap().unwrap();
+    params.iter()
+        .filter_map(|param| map.get(*param).map(|t| format!("{}={}", param, t.as_str().unwrap())))
+        .collect()
+}
+
+const MAX_POOL_PARAMS: &[&str] = &["kernel_size", "stride", "padding", "dilation", "ceil_mode"];
+const AVG_POOL_PARAMS: &[&str] = &["kernel_size", "stride", "padding", "ceil_mode", "count_include_pad"];
+const ADAPTIVE_POOL_PARAMS: &[&str] = &["output_size"];
+
+/// `Resolve` and `PyTorch` for the functional form, `F.max_pool2d(x, kernel_size=2)`
+macro_rules! impl_pool_fn {
+    ($op:ident, $pytorch_name:expr, $rank:expr, $pooling:expr, $params:expr) => {
+        impl Resolve for $op {
+            fn resolve(
+                &self,
+                tenv: &mut TypeEnv,
+                fn_name: &str,
+                arg_ty: Type,
+                _ret_ty: Type,
+                args: Vec<TyFnAppArg>,
+                _inits: Option<Vec<TyFnAppArg>>,
+            ) -> Option<Result<Type, Diag>> {
+                match fn_name {
+                    "forward" => resolve_pool(self.get_name(), $rank, $pooling, tenv, arg_ty, &args),
+                    _ => None,
+                }
+            }
+        }
+
+        impl PyTorch for $op {
+            fn pytorch_name(&self) -> &'static str {
+                $pytorch_name
+            }
+
+            fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
+                let mut buf = String::new();
+                match name {
+                    "forward" => {
+                        write!(buf, "{}", args[0].name.clone().unwrap()).unwrap();
+                        if let Pooling::Global = $pooling {
+                            write!(buf, ", 1").unwrap();
+                        }
+                        for kwarg in gen_pool_kwargs(&args[1..], $params) {
+                            write!(buf, ", {}", kwarg).unwrap();
+                        }
+                        Ok(buf)
+                    }
+                    _ => panic!("{} is not implemented", name),
+                }
+            }
+        }
+    };
+}
+
+/// `Resolve` and `PyTorch` for the module form, `nn.MaxPool2d(kernel_size=2)`
+macro_rules! impl_pool_module {
+    ($op:ident, $pytorch_name:expr, $rank:expr, $pooling:expr, $params:expr) => {
+        impl Resolve for $op {
+            fn resolve(
+                &self,
+                tenv: &mut TypeEnv,
+                fn_name: &str,
+                arg_ty: Type,
+                _ret_ty: Type,
+                _args: Vec<TyFnAppArg>,
+                inits: Option<Vec<TyFnAppArg>>,
+            ) -> Option<Result<Type, Diag>> {
+                match fn_name {
+                    "forward" => resolve_pool(self.get_name(), $rank, $pooling, tenv, arg_ty, &inits?),
+                    _ => None,
+                }
+            }
+        }
+
+        impl PyTorch for $op {
+            fn pytorch_name(&self) -> &'static str {
+                $pytorch_name
+            }
+
+            fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
+                match name {
+                    "new" => Ok(format!("{}({})", self.pytorch_name(), gen_pool_kwargs(args, $params).join(", "))),
+                    "forward" => {
+                        let args: Vec<_> = args.iter().map(|i| i.name.clone().unwrap()).collect();
+                        Ok(args.join(", "))
+                    }
+                    _ => panic!("{} is not implemented", name),
+                }
+            }
+        }
+    };
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct maxpool1d;
+impl_pool_fn!(maxpool1d, "F.max_pool1d", 1, Pooling::Window, MAX_POOL_PARAMS);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct maxpool2d;
+impl_pool_fn!(maxpool2d, "F.max_pool2d", 2, Pooling::Window, MAX_POOL_PARAMS);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct maxpool3d;
+impl_pool_fn!(maxpool3d, "F.max_pool3d", 3, Pooling::Window, MAX_POOL_PARAMS);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct avgpool1d;
+impl_pool_fn!(avgpool1d, "F.avg_pool1d", 1, Pooling::Window, AVG_POOL_PARAMS);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct avgpool2d;
+impl_pool_fn!(avgpool2d, "F.avg_pool2d", 2, Pooling::Window, AVG_POOL_PARAMS);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct avgpool3d;
+impl_pool_fn!(avgpool3d, "F.avg_pool3d", 3, Pooling::Window, AVG_POOL_PARAMS);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct adaptive_avgpool2d;
+impl_pool_fn!(adaptive_avgpool2d, "F.adaptive_avg_pool2d", 2, Pooling::Adaptive, ADAPTIVE_POOL_PARAMS);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct global_avgpool2d;
+impl_pool_fn!(global_avgpool2d, "F.adaptive_avg_pool2d", 2, Pooling::Global, &[]);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+pub struct global_maxpool2d;
+impl_pool_fn!(global_maxpool2d, "F.adaptive_max_pool2d", 2, Pooling::Global, &[]);
+
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+#[new = "(kernel_size: int, stride: int = kernel_size, padding: int = 0, dilation: int = 1, ceil_mode: bool = false) -> self"]
+#[stateful]
+pub struct MaxPool1d;
+impl_pool_module!(MaxPool1d, "nn.MaxPool1d", 1, Pooling::Window, MAX_POOL_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+#[new = "(kernel_size: int2, stride: int2 = kernel_size, padding: int2 = 0, dilation: int2 = 1, ceil_mode: bool = false) -> self"]
+#[stateful]
+pub struct MaxPool2d;
+impl_pool_module!(MaxPool2d, "nn.MaxPool2d", 2, Pooling::Window, MAX_POOL_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+#[new = "(kernel_size: int3, stride: int3 = kernel_size, padding: int3 = 0, dilation: int3 = 1, ceil_mode: bool = false) -> self"]
+#[stateful]
+pub struct MaxPool3d;
+impl_pool_module!(MaxPool3d, "nn.MaxPool3d", 3, Pooling::Window, MAX_POOL_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+#[new = "(kernel_size: int, stride: int = kernel_size, padding: int = 0, ceil_mode: bool = false, count_include_pad: bool = true) -> self"]
+#[stateful]
+pub struct AvgPool1d;
+impl_pool_module!(AvgPool1d, "nn.AvgPool1d", 1, Pooling::Window, AVG_POOL_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+#[new = "(kernel_size: int2, stride: int2 = kernel_size, padding: int2 = 0, ceil_mode: bool = false, count_include_pad: bool = true) -> self"]
+#[stateful]
+pub struct AvgPool2d;
+impl_pool_module!(AvgPool2d, "nn.AvgPool2d", 2, Pooling::Window, AVG_POOL_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+#[new = "(kernel_size: int3, stride: int3 = kernel_size, padding: int3 = 0, ceil_mode: bool = false, count_include_pad: bool = true) -> self"]
+#[stateful]
+pub struct AvgPool3d;
+impl_pool_module!(AvgPool3d, "nn.AvgPool3d", 3, Pooling::Window, AVG_POOL_PARAMS);
+
+#[derive(Debug, Op)]
+#[path = "pool"]
+#[forward = "?() -> unit"]
+#[new = "(output_size: int2) -> self"]
+#[stateful]
+pub struct AdaptiveAvgPool2d;
+impl_pool_module!(AdaptiveAvgPool2d, "nn.AdaptiveAvgPool2d", 2, Pooling::Adaptive, ADAPTIVE_POOL_PARAMS);
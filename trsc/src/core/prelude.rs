@@ -26,6 +26,7 @@ impl Resolve for view {
                 // println!("ret_ty: {:#?}\n, arg_ty: {:#?}", ret_ty, arg_ty);
                 if !arg_ty.is_resolved() { return None; }
                 let args_map = arg_ty.as_args_map()?;
+                let arg_dtype = args_map.get("x")?.dtype()?;
                 let arg_tsr = args_map.get("x")?.as_vec()?;
                 let ret_tsr = ret_ty.as_vec()?;
 
@@ -44,26 +45,35 @@ impl Resolve for view {
                 let ret_prod: i64 = resolved_ret_tsr.iter().product();
                 let arg_prod: i64 = resolved_arg_tsr.iter().product();
 
-                let is_only_one_arg_dim_unresolved = (arg_tsr.len() - resolved_arg_tsr.len()) == 1;
-                if ret_prod == arg_prod && is_only_one_arg_dim_unresolved {
-                    let unresolved_arg_dim = arg_tsr.iter().find(|i| i.as_num().is_none()).unwrap();
-                    let unresolved_ret_dim = ret_tsr.iter().find(|i| i.as_num().is_none()).unwrap();
-                    let modified_ret_ty = ret_tsr
+                let arg_unresolved = arg_tsr.len() - resolved_arg_tsr.len();
+                let ret_unresolved = ret_tsr.len() - resolved_ret_tsr.len();
+                let unresolved_ret_dim = ret_tsr.iter().find(|i| i.as_num().is_none());
+                // the elided dimension is either the one unknown input
+                // dimension or what is left of the input's size
+                let elided = match (arg_unresolved, ret_unresolved) {
+                    (0, 0) if ret_prod == arg_prod => None,
+                    (0, 1) if ret_prod != 0 && arg_prod % ret_prod == 0 => {
+                        Some(Type::ResolvedDim(arg_prod / ret_prod, unresolved_ret_dim?.span()))
+                    }
+                    (1, 1) if ret_prod == arg_prod => {
+                        arg_tsr.iter().find(|i| i.as_num().is_none()).cloned()
+                    }
+                    _ => {
+                        let x_ty = args_map.get("x")?.clone();
+                        return Some(Err(Diag::ViewSizeMismatch(x_ty, ret_ty.clone(), ret_ty.span())));
+                    }
+                };
+                let modified_ret_ty = match elided {
+                    Some(dim) => ret_tsr
                         .iter()
-                        .map(|i|
-                            if i == unresolved_ret_dim {
-                                unresolved_arg_dim
-                            } else {
-                                i
-                            } )
+                        .map(|i| if Some(i) == unresolved_ret_dim { &dim } else { i })
                         .cloned()
-                        .collect();
-                    Some(Ok(
-                        fun!("view", "forward", arg_ty, tsr!(modified_ret_ty))
-                    ))
-                } else {
-                    panic!("{} {}", ret_prod, arg_prod);// ...
-                }
+                        .collect(),
+                    None => ret_tsr.clone(),
+                };
+                Some(Ok(
+                    fun!("view", "forward", arg_ty, tsr!(modified_ret_ty, arg_dtype))
+                ))
             }
             _ => unimplemented!(),
         }
@@ -74,4 +84,38 @@ impl PyTorch for view {
     fn pytorch_name(&self) -> &'static str {
         unimplemented!();
     }
-}
\ No newline at end of file
+}
+#[allow(non_camel_case_types)]
+#[derive(Debug, Op)]
+#[path = "prelude"]
+#[forward = "?() -> unit"]
+pub struct to;
+
+impl Resolve for to {
+    /// output same shape as input with the element type in `to(..)`
+    fn resolve(
+        &self,
+        _tenv: &mut TypeEnv,
+        fn_name: &str,
+        arg_ty: Type,
+        ret_ty: Type,
+        _args: Vec<TyFnAppArg>,
+        _inits: Option<Vec<TyFnAppArg>>,
+    ) -> Option<Result<Type, Diag>> {
+        match fn_name {
+            "forward" => {
+                let x_ty = arg_ty.as_args_map()?.get("x")?.clone();
+                if !x_ty.is_resolved() { return None; }
+                let dims = x_ty.as_vec()?;
+                Some(Ok(fun!("to", "forward", arg_ty, tsr!(dims, ret_ty.dtype()?))))
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl PyTorch for to {
+    fn pytorch_name(&self) -> &'static str {
+        unimplemented!();
+    }
+}
@@ -1,4 +1,4 @@
-use core::{MethodName, Op, PyTorch, Resolve};
+use core::{float_dtype, inits_span, MethodName, Op, PyTorch, Resolve};
 use errors::Diag;
 use span::CSpan;
 use typing::typed_term::TyFnAppArg;
@@ -95,11 +95,75 @@ impl PyTorch for BatchNorm1d {
                 let map = args.to_btreemap().unwrap();
                 write!(buf, "{}(", self.pytorch_name()).unwrap();
                 write!(buf, "num_features={})",
-                    map["num_features"].as_num().unwrap()).unwrap();
+                    map["num_features"].as_str().unwrap()).unwrap();
             }
             "forward" => {
-                // let map = args.to_btreemap().unwrap();
-                write!(buf, "x").unwrap();
+                write!(buf, "{}", args[0].name.clone().unwrap()).unwrap();
+            }
+            _ => unimplemented!(),
+        }
+
+        Ok(buf)
+    }
+}
+
+#[derive(Debug, Op)]
+#[path = "reg"]
+#[new = "(normalized_shape: int) -> self"]
+#[forward = "?(x: tsr0) -> tsr0"]
+#[stateful]
+pub struct LayerNorm;
+
+impl Resolve for LayerNorm {
+    /// normalizes over the last dimension `[..., C] -> [..., C]`
+    fn resolve(
+        &self,
+        tenv: &mut TypeEnv,
+        fn_name: &str,
+        arg_ty: Type,
+        _ret_ty: Type,
+        _args: Vec<TyFnAppArg>,
+        inits: Option<Vec<TyFnAppArg>>,
+    ) -> Option<Result<Type, Diag>> {
+        match fn_name {
+            "forward" => {
+                let inits = inits?;
+                let span = inits_span(&inits, arg_ty.span());
+                let hm = inits.to_btreemap().unwrap();
+                let dim = match hm.get("normalized_shape") {
+                    Some(t) => t.ty(),
+                    None => return Some(Err(Diag::MissingInitArg(self.get_name().to_owned(), "normalized_shape".to_owned(), span))),
+                };
+                let init_span = hm["normalized_shape"].span();
+                let dim = match dim {
+                    Type::INT(_) => hm["normalized_shape"].as_num().map(|n| Type::ResolvedDim(n, init_span))?,
+                    dim => dim.with_span(&init_span),
+                };
+                let leading = tenv.fresh_dims(CSpan::fresh_span());
+                let dtype = float_dtype(arg_ty.as_args_map()?.get("x")?);
+                let ty = Type::TSR(vec![leading, dim], box dtype, CSpan::fresh_span());
+                Some(Ok(fun!(self.get_name(), "forward", args!(arg!("x", ty.clone())), ty)))
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl PyTorch for LayerNorm {
+    fn pytorch_name(&self) -> &'static str {
+        "nn.LayerNorm"
+    }
+    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
+        let mut buf = String::new();
+        match name {
+            "new" => {
+                let map = args.to_btreemap().unwrap();
+                write!(buf, "{}(", self.pytorch_name()).unwrap();
+                write!(buf, "normalized_shape={})",
+                    map["normalized_shape"].as_str().unwrap()).unwrap();
+            }
+            "forward" => {
+                write!(buf, "{}", args[0].name.clone().unwrap()).unwrap();
             }
             _ => unimplemented!(),
         }
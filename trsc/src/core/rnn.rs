@@ -0,0 +1,156 @@
+use core::{float_dtype, inferred_arg, init_dim, inits_span, unwrap_dim, MethodName, Op, PyTorch, Resolve};
+use errors::Diag;
+use span::CSpan;
+use typing::typed_term::{ArgsVecInto, TyFnAppArg};
+use typing::{Type, TypeEnv};
+use parsing::term;
+
+/// `[N, T, input]` to `([N, T, hidden * dirs], state)` where the state is
+/// `(h, c)` for an LSTM and `h` otherwise, each `[layers * dirs, N, hidden]`.
+/// Without `batch_first` the sequence comes first, `[T, N, input]`
+fn resolve_rnn(
+    op: &str,
+    has_cell: bool,
+    tenv: &mut TypeEnv,
+    arg_ty: Type,
+    inits: Option<Vec<TyFnAppArg>>,
+) -> Option<Result<Type, Diag>> {
+    let inits = inits?;
+    let span = inits_span(&inits, arg_ty.span());
+    let map = inits.to_btreemap()?;
+    let x_ty = arg_ty.as_args_map()?.get("x")?.clone();
+
+    let input = match map.get("input_size") {
+        Some(t) => match unwrap_dim(t)? {
+            Ok(dim) => dim,
+            Err(e) => return Some(Err(e)),
+        },
+        // wait until the last dimension of the input is known
+        None => match x_ty.as_vec()?.last()? {
+            dim @ Type::ResolvedDim(..) => dim.clone(),
+            _ => return None,
+        },
+    };
+    let hidden = match init_dim(op, &map, "hidden_size", span)? {
+        Ok(dim) => dim,
+        Err(e) => return Some(Err(e)),
+    };
+    let (layers, layers_span) = match map.get("num_layers") {
+        Some(t) => (t.as_num()?, t.span()),
+        None => (1, CSpan::fresh_span()),
+    };
+    let dirs = match map.get("bidirectional").and_then(|t| t.as_bool()) {
+        Some(true) => 2,
+        _ => 1,
+    };
+    let batch_first = map.get("batch_first").and_then(|t| t.as_bool()).unwrap_or(false);
+
+    let (n, t) = (tenv.fresh_dim(CSpan::fresh_span()), tenv.fresh_dim(CSpan::fresh_span()));
+    let seq = |features: Type| if batch_first {
+        vec![n.clone(), t.clone(), features]
+    } else {
+        vec![t.clone(), n.clone(), features]
+    };
+    let dtype = float_dtype(&x_ty);
+    let features = Type::dim_op(term::Op::Mult, hidden.clone(), Type::ResolvedDim(dirs, CSpan::fresh_span()), CSpan::fresh_span());
+    let h = tsr!(vec![Type::ResolvedDim(layers * dirs, layers_span), n.clone(), hidden], dtype.clone());
+    let state = if has_cell {
+        Type::Tuple(vec![h.clone(), h], CSpan::fresh_span())
+    } else {
+        h
+    };
+
+    Some(Ok(fun!(
+        op,
+        "forward",
+        args!(arg!("x", tsr!(seq(input), dtype.clone()))),
+        Type::Tuple(vec![tsr!(seq(features), dtype), state], CSpan::fresh_span())
+    )))
+}
+
+/// `input_size` is the last dimension of the input
+fn infer_input_size(fn_ty: &Type, inits: &[TyFnAppArg]) -> Vec<TyFnAppArg> {
+    if inits.iter().any(|a| a.name == Some("input_size".to_owned())) {
+        return vec![];
+    }
+    fn_ty.first_arg_ty()
+        .and_then(|x_ty| x_ty.as_vec())
+        .and_then(|dims| inferred_arg("input_size", dims.last()?))
+        .into_iter()
+        .collect()
+}
+
+/// `Resolve` and `PyTorch` for a recurrent layer, `forward` returns
+/// the output sequence together with the final state
+macro_rules! impl_rnn {
+    ($op:ident, $pytorch_name:expr, $has_cell:expr) => {
+        impl Resolve for $op {
+            fn resolve(
+                &self,
+                tenv: &mut TypeEnv,
+                fn_name: &str,
+                arg_ty: Type,
+                _ret_ty: Type,
+                _args: Vec<TyFnAppArg>,
+                inits: Option<Vec<TyFnAppArg>>,
+            ) -> Option<Result<Type, Diag>> {
+                match fn_name {
+                    "forward" => resolve_rnn(self.get_name(), $has_cell, tenv, arg_ty, inits),
+                    _ => unimplemented!(),
+                }
+            }
+
+            fn infer_inits(&self, fn_ty: &Type, inits: &[TyFnAppArg]) -> Vec<TyFnAppArg> {
+                infer_input_size(fn_ty, inits)
+            }
+        }
+
+        impl PyTorch for $op {
+            fn pytorch_name(&self) -> &'static str {
+                $pytorch_name
+            }
+
+            fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
+                match name {
+                    "new" => {
+                        let map = args.to_btreemap().unwrap();
+                        let params = ["input_size", "hidden_size", "num_layers", "bidirectional", "batch_first"];
+                        let kwargs: Vec<_> = params.iter()
+                            .filter_map(|p| map.get(*p).map(|t| format!("{}={}", p, t.as_str().unwrap())))
+                            .collect();
+                        Ok(format!("{}({})", self.pytorch_name(), kwargs.join(", ")))
+                    }
+                    "forward" => {
+                        let args: Vec<_> = args.iter().map(|i| i.name.clone().unwrap()).collect();
+                        Ok(args.join(", "))
+                    }
+                    _ => panic!("{} is not implemented", name),
+                }
+            }
+        }
+    };
+}
+
+#[derive(Debug, Op)]
+#[path = "rnn"]
+#[forward = "?() -> unit"]
+#[new = "(input_size: int = ?, hidden_size: int, num_layers: int = 1, bidirectional: bool = false, batch_first: bool = false) -> self"]
+#[stateful]
+pub struct LSTM;
+impl_rnn!(LSTM, "nn.LSTM", true);
+
+#[derive(Debug, Op)]
+#[path = "rnn"]
+#[forward = "?() -> unit"]
+#[new = "(input_size: int = ?, hidden_size: int, num_layers: int = 1, bidirectional: bool = false, batch_first: bool = false) -> self"]
+#[stateful]
+pub struct GRU;
+impl_rnn!(GRU, "nn.GRU", false);
+
+#[derive(Debug, Op)]
+#[path = "rnn"]
+#[forward = "?() -> unit"]
+#[new = "(input_size: int = ?, hidden_size: int, num_layers: int = 1, bidirectional: bool = false, batch_first: bool = false) -> self"]
+#[stateful]
+pub struct RNN;
+impl_rnn!(RNN, "nn.RNN", false);
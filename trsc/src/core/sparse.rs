@@ -0,0 +1,152 @@
+use core::{init_dim, inits_span, MethodName, Op, PyTorch, Resolve};
+use errors::Diag;
+use span::CSpan;
+use typing::typed_term::{ArgsVecInto, TyFnAppArg, TyTerm};
+use typing::{Type, TypeEnv};
+
+#[derive(Debug, Op)]
+#[path = "sparse"]
+#[new = "(num_embeddings: int, embedding_dim: int, padding_idx: int = none) -> self"]
+#[forward = "?() -> unit"]
+#[init_pretrained = "?() -> unit"]
+#[stateful]
+pub struct Embedding;
+
+impl Resolve for Embedding {
+    /// looks up integer indices `[..., T] -> [..., T, embedding_dim]`,
+    /// `init_pretrained` loads `[num_embeddings, embedding_dim]` vectors
+    fn resolve(
+        &self,
+        tenv: &mut TypeEnv,
+        fn_name: &str,
+        arg_ty: Type,
+        _ret_ty: Type,
+        args: Vec<TyFnAppArg>,
+        inits: Option<Vec<TyFnAppArg>>,
+    ) -> Option<Result<Type, Diag>> {
+        let inits = inits?;
+        let span = inits_span(&inits, arg_ty.span());
+        let hm = inits.to_btreemap()?;
+        let num_embeddings = match init_dim(self.get_name(), &hm, "num_embeddings", span)? {
+            Ok(dim) => dim,
+            Err(e) => return Some(Err(e)),
+        };
+        let embedding_dim = match init_dim(self.get_name(), &hm, "embedding_dim", span)? {
+            Ok(dim) => dim,
+            Err(e) => return Some(Err(e)),
+        };
+
+        match fn_name {
+            "forward" => {
+                // a negative index counts from the end of the vocabulary
+                if let (Some(t), Some(n)) = (hm.get("padding_idx"), num_embeddings.as_num()) {
+                    let idx = t.as_num()?;
+                    if idx < -n || idx >= n {
+                        return Some(Err(Diag::IndexOutOfRange(format!("padding_idx={}", idx), -n, n, t.span())));
+                    }
+                }
+                // literal indices `(3, 14, 15) |> emb` must be within the vocabulary
+                let literal = args.iter()
+                    .find(|a| a.name == Some("x".to_owned()))
+                    .and_then(|a| literal_indices(&a.arg).map(|idxs| (idxs, a)));
+                if let Some((idxs, x)) = literal {
+                    if let Some(n) = num_embeddings.as_num() {
+                        if let Some(idx) = idxs.iter().find(|&&i| i < 0 || i >= n) {
+                            return Some(Err(Diag::IndexOutOfRange(idx.to_string(), 0, n, x.span)));
+                        }
+                    }
+                    let mut dims = match strip_expr(&x.arg) {
+                        TyTerm::TyTuple(..) => vec![Type::ResolvedDim(idxs.len() as i64, x.span)],
+                        _ => vec![],
+                    };
+                    dims.push(embedding_dim);
+                    return Some(Ok(fun!(
+                        self.get_name(),
+                        "forward",
+                        args!(arg!("x", x.arg.ty())),
+                        tsr!(dims, dtype!(F32))
+                    )));
+                }
+                let leading = tenv.fresh_dims(CSpan::fresh_span());
+                Some(Ok(fun!(
+                    self.get_name(),
+                    "forward",
+                    args!(arg!("x", tsr!(vec![leading.clone()], dtype!(I64)))),
+                    tsr!(vec![leading, embedding_dim], dtype!(F32))
+                )))
+            }
+            "init_pretrained" => {
+                Some(Ok(fun!(
+                    self.get_name(),
+                    "init_pretrained",
+                    args!(
+                        arg!("vectors", tsr!(vec![num_embeddings, embedding_dim], dtype!(F32))),
+                        arg!("freeze", Type::BOOL(CSpan::fresh_span()))
+                    ),
+                    Type::Unit(CSpan::fresh_span())
+                )))
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl PyTorch for Embedding {
+    fn pytorch_name(&self) -> &'static str {
+        "nn.Embedding"
+    }
+
+    fn gen_fn_app(&self, name: &str, args: &[TyFnAppArg]) -> Result<String, Diag> {
+        match name {
+            "new" => {
+                let map = args.to_btreemap().unwrap();
+                let kwargs: Vec<_> = ["num_embeddings", "embedding_dim", "padding_idx"].iter()
+                    .filter_map(|p| map.get(*p).map(|t| format!("{}={}", p, t.as_str().unwrap())))
+                    .collect();
+                Ok(format!("{}({})", self.pytorch_name(), kwargs.join(", ")))
+            }
+            "forward" => {
+                let args: Vec<_> = args.iter()
+                    .map(|i| match literal_indices(&i.arg) {
+                        Some(_) => format!("torch.tensor({})", i.arg.as_str().unwrap()),
+                        None => i.name.clone().unwrap(),
+                    })
+                    .collect();
+                Ok(args.join(", "))
+            }
+            // the module comes first, then the vectors which stay fixed unless `freeze=false`
+            "init_pretrained" => {
+                let module = args[0].name.clone().unwrap();
+                let vectors = args[1].name.clone().unwrap();
+                let trainable = args[2..].iter()
+                    .find(|a| a.name == Some("freeze".to_owned()))
+                    .and_then(|a| a.arg.as_bool())
+                    .map_or(false, |freeze| !freeze);
+                let requires_grad = if trainable { "True" } else { "False" };
+                Ok(format!("{}.weight = nn.Parameter({}, requires_grad={})", module, vectors, requires_grad))
+            }
+            _ => panic!("{} is not implemented", name),
+        }
+    }
+}
+
+fn strip_expr(term: &TyTerm) -> &TyTerm {
+    match term {
+        TyTerm::TyExpr(t, ..) => strip_expr(t),
+        _ => term,
+    }
+}
+
+/// an index `3` or a tuple of indices `(3, 14, 15)` written in the source
+fn literal_indices(term: &TyTerm) -> Option<Vec<i64>> {
+    match strip_expr(term) {
+        TyTerm::TyInteger(_, i, _) => Some(vec![*i]),
+        TyTerm::TyTuple(_, ts, _) => ts.iter()
+            .map(|t| match strip_expr(t) {
+                TyTerm::TyInteger(_, i, _) => Some(*i),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
@@ -2,6 +2,7 @@ use codespan_reporting::{Diagnostic, Label, Severity};
 use typing::Type;
 use codespan::CodeMap;
 use codespan::{ByteSpan, LineIndex};
+use span::CSpan;
 
 #[derive(Debug, Clone)]
 pub enum Diag {
@@ -14,6 +15,28 @@ pub enum Diag {
     DuplicateVarInScope(String, Type, Type),
     TypeError(Type, Type),
     EllisionError(String, ByteSpan),
+    UnboundedLoop(Type, ByteSpan),
+    DescendingLoop(i64, i64, ByteSpan),
+    NotAModuleList(String, ByteSpan),
+    UnindexedModuleList(String, i64, ByteSpan),
+    DimArgsMismatch(String, usize, usize, ByteSpan),
+    DTypeMismatch(Type, Type),
+    UnresolvedShape(String, Type, ByteSpan),
+    MissingInitArg(String, String, ByteSpan),
+    UnknownInitArg(String, String, ByteSpan),
+    InitArgTypeMismatch(String, String, Type, Type),
+    ChannelMismatch(Type, Type, ByteSpan),
+    ViewSizeMismatch(Type, Type, ByteSpan),
+    WrongArgName(String, String, ByteSpan),
+    CircularType(Type, Type),
+    IndivisibleGroups(String, i64, i64, ByteSpan),
+    IndexOutOfRange(String, i64, i64, ByteSpan),
+    DimOverflow(Type, ByteSpan),
+    DivisionByZero(Type, ByteSpan),
+    ArgTooSmall(String, i64, i64, ByteSpan),
+    NonPositiveOutput(i64, i64, ByteSpan),
+    OutputPaddingTooLarge(i64, i64, i64, ByteSpan),
+    PoolPaddingTooLarge(i64, i64, ByteSpan),
 }
 
 impl Diag {
@@ -21,21 +44,27 @@ impl Diag {
         use self::Diag::*;
         match self {
             DimensionMismatch(Type::ResolvedDim(v1, s1), Type::ResolvedDim(v2,s2)) => {
-                Diagnostic::new(
+                let d = Diagnostic::new(
                     Severity::Error,
                     format!("Dimension mismatch: {} != {}", v1, v2),
-                )
-                .with_label(Label::new_primary(*s1))
-                .with_label(Label::new_primary(*s2))
+                );
+                with_origin(d, *s1, *s2, format!("`{}` comes from here", v2))
             }
 
-            RankMismatch(Type::TSR(dims1, s1), Type::TSR(dims2, s2)) => {
-                Diagnostic::new(
+            DimensionMismatch(ty1, ty2) => {
+                let d = Diagnostic::new(
+                    Severity::Error,
+                    format!("Dimension mismatch: {:?} != {:?}", ty1, ty2),
+                );
+                with_origin(d, ty1.span(), ty2.span(), format!("`{:?}` comes from here", ty2))
+            }
+
+            RankMismatch(Type::TSR(dims1, _, s1), Type::TSR(dims2, _, s2)) => {
+                let d = Diagnostic::new(
                     Severity::Error,
                     format!("Tensor rank mismatch: rank({:?}) != rank({:?})", dims1, dims2),
-                )
-                .with_label(Label::new_primary(*s1))
-                .with_label(Label::new_primary(*s2))
+                );
+                with_origin(d, *s1, *s2, format!("rank {} comes from here", dims2.len()))
             },
 
             ParseError(msg, sp) => {
@@ -44,7 +73,7 @@ impl Diag {
                 let idx = sp.start();
                 let file = code_map.find_file(idx).unwrap();
                 let line = file.find_line(idx).unwrap();
-                let prev_line = line.to_usize() - 1;
+                let prev_line = line.to_usize().saturating_sub(1);
                 let prev_line_span = file.line_span(LineIndex(prev_line as u32)).unwrap();
                 Diagnostic::new(
                     Severity::Error,
@@ -80,12 +109,12 @@ impl Diag {
             }
 
             TypeError(ty1, ty2) => {
-                Diagnostic::new(
+                let d = Diagnostic::new(
                     Severity::Error,
                     format!("Type mismatch: {:?}, {:?}", ty1, ty2),
-                )
-                .with_label(Label::new_primary(ty1.span()))
-                .with_label(Label::new_primary(ty2.span()))
+                );
+                let d = with_origin(d, ty1.span(), ty2.span(), format!("`{:?}` comes from here", ty2));
+                with_dim_origins(d, ty1, ty2)
             }
 
             EllisionError(msg, span) => {
@@ -96,8 +125,224 @@ impl Diag {
                 .with_label(Label::new_primary(*span))
             }
 
+            UnboundedLoop(ty, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Loop bound is not known at compile time: {:?}", ty),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            DescendingLoop(from, to, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Loop range {}..{} counts down", from, to),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            NotAModuleList(name, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`{}` is not a list of modules and cannot be indexed", name),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            UnindexedModuleList(name, count, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`{}` is a list of {} modules, pick one with `{}[i]`", name, count, name),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            DimArgsMismatch(name, expected, supplied, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`{}` takes {} dimension arguments but {} were supplied", name, expected, supplied),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            DTypeMismatch(ts1, ts2) => {
+                let d = Diagnostic::new(
+                    Severity::Error,
+                    format!("Tensor dtype mismatch: {:?} != {:?}", ts1.dtype().unwrap(), ts2.dtype().unwrap()),
+                );
+                with_origin(d, ts1.span(), ts2.span(), format!("`{:?}` comes from here", ts2.dtype().unwrap()))
+            }
+
+            UnresolvedShape(name, input, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Could not infer shape of `{}` from input {:?}", name, input),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            MissingInitArg(op, param, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Initialize `{}` with parameter `{}=`", op, param),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            UnknownInitArg(op, param, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`{}` has no parameter `{}`", op, param),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            InitArgTypeMismatch(op, param, expected, supplied) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Parameter `{}` of `{}` expects {:?} but {:?} was supplied", param, op, expected, supplied),
+                )
+                .with_label(Label::new_primary(supplied.span()))
+            }
+
+            ChannelMismatch(in_ch, c_in, span) => {
+                let d = Diagnostic::new(
+                    Severity::Error,
+                    format!("Channel mismatch: expected {:?} input channels but the input has {:?}", in_ch, c_in),
+                );
+                let d = with_origin(d, *span, in_ch.span(), "`in_ch` is set here".to_owned());
+                with_secondary(d, *span, c_in.span(), format!("`{:?}` comes from here", c_in))
+            }
+
+            ViewSizeMismatch(arg, ret, span) => {
+                let d = Diagnostic::new(
+                    Severity::Error,
+                    format!("Cannot view tensor of shape {:?} as {:?}", arg, ret),
+                );
+                with_origin(d, *span, arg.span(), format!("`{:?}` comes from here", arg))
+            }
+
+            WrongArgName(expected, supplied, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Wrong argument name: expected `{}` but `{}` was supplied", expected, supplied),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            CircularType(var, ty) => {
+                let d = Diagnostic::new(
+                    Severity::Error,
+                    format!("Circular type: {:?} occurs in {:?}", var, ty),
+                );
+                with_origin(d, ty.span(), var.span(), format!("`{:?}` comes from here", var))
+            }
+
+            IndivisibleGroups(param, channels, groups, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`groups={}` does not divide `{}={}`", groups, param, channels),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            IndexOutOfRange(index, from, to, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`{}` is out of range {}..{}", index, from, to),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            DimOverflow(ty, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Dimension overflows: {:?}", ty),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            DivisionByZero(ty, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Division by zero: {:?}", ty),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            ArgTooSmall(param, value, min, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`{}={}` is less than {}", param, value, min),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            NonPositiveOutput(input, output, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("Input size {} gives an output size of {}", input, output),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            OutputPaddingTooLarge(output_padding, stride, dilation, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!(
+                        "`output_padding={}` must be smaller than `stride={}` or `dilation={}`",
+                        output_padding, stride, dilation,
+                    ),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
+            PoolPaddingTooLarge(padding, kernel_size, span) => {
+                Diagnostic::new(
+                    Severity::Error,
+                    format!("`padding={}` must be at most half of `kernel_size={}`", padding, kernel_size),
+                )
+                .with_label(Label::new_primary(*span))
+            }
+
             _ => unimplemented!(),
         }
     }
 
 }
+
+/// labels the offending span and, if it is somewhere else,
+/// where the conflicting type came from
+fn with_origin(d: Diagnostic, primary: ByteSpan, origin: ByteSpan, msg: String) -> Diagnostic {
+    with_secondary(d.with_label(Label::new_primary(primary)), primary, origin, msg)
+}
+
+fn with_secondary(d: Diagnostic, primary: ByteSpan, origin: ByteSpan, msg: String) -> Diagnostic {
+    if origin == primary || CSpan::is_fresh(&origin) {
+        d
+    } else {
+        d.with_label(Label::new_secondary(origin).with_message(msg))
+    }
+}
+
+/// for two tensors of the same rank, labels where each mismatched dimension came from
+fn with_dim_origins(mut d: Diagnostic, ts1: &Type, ts2: &Type) -> Diagnostic {
+    if let (Some(dims1), Some(dims2)) = (ts1.as_vec(), ts2.as_vec()) {
+        if dims1.len() != dims2.len() {
+            return d;
+        }
+        for (a, b) in dims1.iter().zip(dims2.iter()) {
+            match (a.as_num(), b.as_num()) {
+                (Some(x), Some(y)) if x != y => {
+                    for (dim, n) in vec![(a, x), (b, y)] {
+                        if !CSpan::is_fresh(&dim.span()) {
+                            d = d.with_label(Label::new_secondary(dim.span()).with_message(format!("`{}` comes from here", n)));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    d
+}
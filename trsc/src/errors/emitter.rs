@@ -1,5 +1,5 @@
 use std::str::FromStr;
-use codespan::CodeMap;
+use codespan::{ByteSpan, CodeMap};
 use codespan_reporting::termcolor::StandardStream;
 use codespan_reporting::{emit, ColorArg, Diagnostic, Severity };
 use super::diagnostic::Diag;
@@ -21,6 +21,11 @@ impl Emitter {
         }
     }
 
+    /// registers a source file so that diagnostics can point into it
+    pub fn add_file(&mut self, name: String, src: String) -> ByteSpan {
+        self.code_map.add_filemap(name.into(), src).span()
+    }
+
     pub fn add(&mut self, e: Diag) {
         self.errs.push(e);
     }
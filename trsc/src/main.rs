@@ -32,6 +32,9 @@
 ///       variable from the constraint set. The process is iterated until one of these conditions are met:
 ///       a) all type variable are exhausted. b) equivalence that can never happen. c) circular
 ///       type dependence (handled by occurs check).
+///       Constraints that depend on the shape of their inputs, such as the output of
+///       a convolution, are deferred and retried whenever the substitution refines
+///       their inputs. (handled in unifier.rs)
 ///
 ///   c. Generate Substitutions
 ///       Now after the unification is complete, the function returns a list of substitutions that
@@ -54,6 +57,8 @@ mod typing;
 extern crate pest_derive;
 #[macro_use]
 extern crate maplit;
+#[macro_use]
+extern crate lazy_static;
 
 extern crate codespan;
 extern crate clap;
@@ -74,13 +79,13 @@ use typing::type_env::TypeEnv;
 use typing::Type;
 use typing::inferred_ast::subs;
 use errors::{Emitter};
-use parsing::ast_builder::ASTBuilder;
-use span::CSpan;
+use parsing::loader::Loader;
 
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
 use std::process::exit;
 
 use codespan::CodeMap;
@@ -113,61 +118,31 @@ fn main() {
     let mut src = String::new();
     file.read_to_string(&mut src).expect("Unable to read the file");
     // -------------------- create emitter --------------------
-    let mut code_map = CodeMap::new();
-    let file_map = code_map.add_filemap(fname.to_owned().into(), src.clone());
-    let emitter = Rc::new(RefCell::new(Emitter::new(code_map, print_ast)));
-    // --------------- parse into untyped ast   -----------------
-    let cspan = CSpan::new(file_map.span());
-    let builder = ASTBuilder::new(Rc::clone(&emitter), cspan);
-    let parsed_terms = builder.parse_str(&src);
+    let emitter = Rc::new(RefCell::new(Emitter::new(CodeMap::new(), print_ast)));
+    let core = Rc::new(RefCell::new(core::Core::new()));
+    // ------- parse into untyped ast, along with used files ---------
+    let loader = Loader::new(Rc::clone(&emitter), Rc::clone(&core), Path::new(fname));
+    let parsed_terms = loader.load(fname, &src);
     let program = parsed_terms
         .unwrap_or_else(||{ emitter.borrow().print_errs(); exit(-1); });
+    emitter.borrow().print_errs();
     // ------------- annotate ast with type vars --------------
-    let core = Rc::new(RefCell::new(core::Core::new()));
     let tenv = Rc::new(RefCell::new(TypeEnv::new(core.clone())));
     let annotator = Annotator::new(Rc::clone(&emitter), Rc::clone(&tenv));
     let ast = annotator.annotate(&program);
     emitter.borrow().print_errs();
     // println!("{:#?}", ast);
     // println!("initial tenv: {:#?}", tenv);
-    // ------------ first unitfication pass ---------------
+    // ------------------ collect constraints ----------------------
     let mut cs = Constraints::new(Rc::clone(&emitter), Rc::clone(&tenv));
     cs.collect(&ast);
+    emitter.borrow().print_errs();
+    // ------ unify, resolving op constraints as their inputs are known -------
     let mut unifier = Unifier::new(Rc::clone(&emitter), Rc::clone(&tenv));
-    let mut last_sub = unifier.unify(cs.clone());
+    let mut sub = unifier.solve(cs);
     emitter.borrow().print_errs();
-    // println!("{:#?}", last_sub);
-
-    // ------------ resolve module constraints until it stabilizes ----------
-    let mut last_ast = subs(&ast, &mut last_sub);;
-    let em_clone = emitter.clone();
-    let tenv_clone = tenv.clone();
-    let resolve_ast = move || {
-        let mut i = 0;
-        loop {
-            // collect constraints
-            let mut new_cs = Constraints::new(Rc::clone(&em_clone), Rc::clone(&tenv_clone));
-            new_cs.collect(&last_ast);
-            em_clone.borrow().print_errs();
-            // unify constraints
-            let mut new_unifier = Unifier::new(Rc::clone(&em_clone), Rc::clone(&tenv_clone));
-            let mut new_sub = new_unifier.unify(new_cs.clone());
-            em_clone.borrow().print_errs();
-            let temp_ast = subs(&last_ast, &mut new_sub);
-            if temp_ast != last_ast {
-                last_ast = temp_ast;
-                i += 1;
-                if i > 1_000_000 {
-                    println!("Error: does not halt");
-                    exit(1);
-                }
-
-                continue;
-            }
-            return last_ast;
-        }
-    };
-    let final_ast = resolve_ast();
+    // println!("{:#?}", sub);
+    let final_ast = subs(&ast, &mut sub);
     if print_ast {
         println!("{:#?}", final_ast);
         exit(0);
@@ -1,20 +1,30 @@
 /// builds untyped AST from token tree.
 ///
-use codespan::{ByteSpan, Span, ByteIndex};
+use codespan::ByteSpan;
 use parsing::grammar::Rule::*;
 use parsing::grammar::{Rule, TensorScriptParser};
 use parsing::term::{Decl, FieldAccess, FnApp, FnAppArg, FnDecl, FnDeclParam, FnTySig, GraphDecl,
-                   AliasAssign, NodeDecl, TensorTy, Term, UseStmt, ViewFn, WeightsAssign,
+                   AliasAssign, CastFn, DType, NodeDecl, Op, Pattern, TensorTy, Term, UseStmt, ViewFn, WeightsAssign,
                    WeightsDecl};
-use pest::iterators::Pair;
+use pest::iterators::{Pair, Pairs};
 use pest::Parser;
 use pest::Error as PestError;
+use pest::prec_climber::{Assoc, Operator, PrecClimber};
 use span::CSpan;
 use errors::{Diag, Emitter};
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::process::exit;
 
+lazy_static! {
+    /// operator precedence of binary ops, from loosest to tightest
+    static ref PREC_CLIMBER: PrecClimber<Rule> = PrecClimber::new(vec![
+        Operator::new(op_add, Assoc::Left) | Operator::new(op_sub, Assoc::Left),
+        Operator::new(op_mult, Assoc::Left) | Operator::new(op_div, Assoc::Left) | Operator::new(op_mod, Assoc::Left),
+        Operator::new(op_expo, Assoc::Right),
+    ]);
+}
+
 pub struct ASTBuilder {
     emitter: Rc<RefCell<Emitter>>,
     cspan: CSpan,
@@ -41,7 +51,7 @@ impl ASTBuilder {
                 let err = match e0 {
                     semicolon => {
                         Diag::ParseError("Missing semicolon".to_owned(),
-                            Span::new(ByteIndex(pos.pos() as u32 - 1) , ByteIndex(pos.pos() as u32 - 1 ))
+                            self.cspan.convert_pos(pos.pos().saturating_sub(1))
                         )
                     }
                     _ => panic!("{}", e),
@@ -83,16 +93,23 @@ impl ASTBuilder {
             // node_decl_body => build_node_decl_body(pair),
             int_lit => self.build_int_lit(pair),
             float_lit => self.build_float_lit(pair),
+            bool_lit => self.build_bool_lit(pair),
             graph_decl_body => self.build_graph_decl_body(pair),
 
             fn_decls => self.build_fn_decls(pair),
             stmts => self.build_stmts(pair),
 
             stmt => self.build_stmt(pair),
+            let_binding => self.build_let_binding(pair),
+            conditional => self.build_conditional(pair),
+            for_loop => self.build_for_loop(pair),
+            assignment => self.build_assignment(pair),
+            index => self.build_index(pair),
             expr => self.build_expr(pair),
             tuple => self.build_tuple(pair),
             block => self.build_block(pair),
             pipes => self.build_pipes(pair),
+            arith_expr => self.build_arith_expr(pair),
             semicolon => Ok(Term::None),
             _ => unexpected_token(&pair),
         }
@@ -119,13 +136,120 @@ impl ASTBuilder {
             self.consume(possible_expr?)?
         };
 
+        let mut body = self.consume(statements?)?;
+        // an `if`/`else` at the end of a block is its value
+        let ret = match (ret, &mut body) {
+            (Term::None, Term::List(ref mut vals)) if vals.last().map_or(false, is_if_else) => {
+                match vals.pop() {
+                    Some(Term::Stmt(box Term::List(mut vals), _)) => vals.pop().unwrap(),
+                    _ => unreachable!(),
+                }
+            }
+            (ret, _) => ret,
+        };
+
         Ok(Term::Block {
-            stmts: Box::new(self.consume(statements?)?),
+            stmts: Box::new(body),
             ret: Box::new(ret),
             span: sp,
         })
     }
 
+    fn build_assignment(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let sp = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let name = eat!(tokens, ident, "Failed to parse assignment target", sp)?;
+        let _op = eat!(tokens, op_assign, "Failed to parse `=`", sp)?;
+        let rhs = eat!(tokens, expr, "Failed to parse assignment expression", sp)?;
+        Ok(Term::Assign(
+            name.as_str().to_owned(),
+            Box::new(self.consume(rhs)?),
+            sp,
+        ))
+    }
+
+    fn build_index(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let sp = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let name = eat!(tokens, ident, "Failed to parse indexed name", sp)?;
+        let idx = eat!(tokens, "Failed to parse index", sp)?;
+        let idx = if idx.as_rule() == ident {
+            Term::Ident(idx.as_str().to_owned(), self.cspan.convert_span(&idx.into_span()))
+        } else {
+            self.consume(idx)?
+        };
+        Ok(Term::Index(name.as_str().to_owned(), Box::new(idx), sp))
+    }
+
+    /// returns the loop variable and the bounds of `for i in a..b`
+    fn build_for_head(&self, tokens: &mut Pairs<Rule>, sp: ByteSpan) -> Result<(String, Term, Term), Diag> {
+        let _for = eat!(tokens, for_lit, "Failed to parse `for`", sp)?;
+        let var = eat!(tokens, ident, "Failed to parse loop variable", sp)?;
+        let mut range = eat!(tokens, for_range, "Failed to parse loop range", sp)?.into_inner();
+        let from = eat!(range, dim_expr, "Failed to parse loop start", sp)?;
+        let to = eat!(range, dim_expr, "Failed to parse loop end", sp)?;
+        Ok((var.as_str().to_owned(), self.build_dim_expr(from)?, self.build_dim_expr(to)?))
+    }
+
+    fn build_for_loop(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let sp = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let (var, from, to) = self.build_for_head(&mut tokens, sp)?;
+        let body = eat!(tokens, block, "Failed to parse loop body", sp)?;
+        Ok(Term::ForLoop {
+            var,
+            from: Box::new(from),
+            to: Box::new(to),
+            body: Box::new(self.consume(body)?),
+            span: sp,
+        })
+    }
+
+    /// every module declared in the loop is repeated `to - from` times
+    fn build_weights_for(&self, pair: Pair<Rule>) -> Result<Vec<WeightsAssign>, Diag> {
+        let sp = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let (var, from, to) = self.build_for_head(&mut tokens, sp)?;
+        tokens
+            .map(|p| {
+                let mut w_assign = self.build_weights_assign(p, Some(&var))?;
+                w_assign.range = Some((from.clone(), to.clone()));
+                Ok(w_assign)
+            })
+            .collect()
+    }
+
+    fn build_conditional(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let sp = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let _if = eat!(tokens, if_lit, "Failed to parse `if`", sp)?;
+        let mut branches = vec![];
+        let mut els = Term::None;
+        loop {
+            let cond = eat!(tokens, expr, "Failed to parse condition", sp)?;
+            let then = eat!(tokens, block, "Failed to parse if block", sp)?;
+            branches.push((self.consume(cond)?, self.consume(then)?));
+            match tokens.next() {
+                None => break,
+                Some(ref t) if t.as_rule() == op_else_if => continue,
+                Some(_) => {
+                    let els_block = eat!(tokens, block, "Failed to parse else block", sp)?;
+                    els = self.consume(els_block)?;
+                    break;
+                }
+            }
+        }
+        // `if a {} else if b {} else {}` is `if a {} else { if b {} else {} }`
+        Ok(branches.into_iter().rev().fold(els, |els, (cond, then)| {
+            Term::Conditional {
+                cond: Box::new(cond),
+                then: Box::new(then),
+                els: Box::new(els),
+                span: sp,
+            }
+        }))
+    }
+
     fn build_fn_app_param(&self, pair: Pair<Rule>) -> Result<Vec<FnAppArg>, Diag> {
         let sp = self.cspan.convert_span(&pair.clone().into_span());
         let mut tokens = pair.into_inner();
@@ -162,6 +286,11 @@ impl ASTBuilder {
         let mut tokens = pair.into_inner();
         let p = tokens.next().unwrap();
         assert!(tokens.next().is_none());
+        let val = self.build_expr_item(p)?;
+        Ok(Term::Expr(Box::new(val), sp))
+    }
+
+    fn build_expr_item(&self, p: Pair<Rule>) -> Result<Term, Diag> {
         let val = match p.as_rule() {
             field_access => Term::FieldAccess(self.build_field_access(p).unwrap()),
             fn_app => Term::FnApp(self.build_fn_app(p).unwrap()),
@@ -171,7 +300,46 @@ impl ASTBuilder {
             }
             _ => self.consume(p).unwrap(),
         };
-        Ok(Term::Expr(Box::new(val), sp))
+        Ok(val)
+    }
+
+    fn build_arith_expr(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let tokens = pair.into_inner();
+        PREC_CLIMBER.climb(
+            tokens,
+            |p| self.build_expr_item(p),
+            |lhs, op, rhs| {
+                let sp = self.cspan.convert_span(&op.clone().into_span());
+                Ok(Term::BinOp(build_op(&op), Box::new(lhs?), Box::new(rhs?), sp))
+            },
+        )
+    }
+
+    /// dimension arithmetic in `dim` declarations and tensor signatures
+    fn build_dim_expr(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let tokens = pair.into_inner();
+        PREC_CLIMBER.climb(
+            tokens,
+            |p| {
+                let sp = self.cspan.convert_span(&p.clone().into_span());
+                match p.as_rule() {
+                    int_lit => self.build_int_lit(p),
+                    ty_ident => Ok(Term::Ident(p.as_str().to_owned(), sp)),
+                    dim_expr => self.build_dim_expr(p),
+                    _ => unexpected_token(&p),
+                }
+            },
+            |lhs, op, rhs| {
+                let sp = self.cspan.convert_span(&op.clone().into_span());
+                Ok(Term::BinOp(build_op(&op), Box::new(lhs?), Box::new(rhs?), sp))
+            },
+        )
+    }
+
+    fn build_ty_dims(&self, pair: Pair<Rule>) -> Vec<Term> {
+        pair.into_inner()
+            .map(|p| self.build_dim_expr(p).unwrap())
+            .collect()
     }
 
     fn build_stmt(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
@@ -181,6 +349,27 @@ impl ASTBuilder {
         Ok(Term::Stmt(Box::new(Term::List(vals)), sp))
     }
 
+    fn build_let_binding(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let sp = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let _let = eat!(tokens, let_lit, "Failed to parse `let`", sp)?;
+        let pat = eat!(tokens, "Failed to parse let binding name", sp)?;
+        let _op = eat!(tokens, op_assign, "Failed to parse `=`", sp)?;
+        let rhs = Box::new(self.consume(eat!(tokens, expr, "Failed to parse let binding expression", sp)?)?);
+        match self.build_let_pattern(pat) {
+            Pattern::Tuple(elems) => Ok(Term::LetTuple(elems, rhs, sp)),
+            Pattern::Ident(name) => Ok(Term::Let(name, rhs, sp)),
+        }
+    }
+
+    fn build_let_pattern(&self, pair: Pair<Rule>) -> Pattern {
+        if pair.as_rule() == let_pattern {
+            Pattern::Tuple(pair.into_inner().map(|p| self.build_let_pattern(p)).collect())
+        } else {
+            Pattern::Ident(pair.as_str().to_owned())
+        }
+    }
+
     fn build_fn_decl(&self, pair: Pair<Rule>) -> Result<FnDecl, Diag> {
         let sp = self.cspan.convert_span(&pair.clone().into_span());
         let mut tokens = pair.into_inner();
@@ -196,11 +385,7 @@ impl ASTBuilder {
                 let return_ty = {
                     let temp = eat!(fn_sig_tokens, ty_sig, "Function does not have a type signature", sp);
                     let temp = temp?.into_inner().next().unwrap();
-                    if temp.as_rule() == ident {
-                        TensorTy::Tensor(temp.as_str().to_owned(), sp)
-                    } else {
-                        TensorTy::Generic(to_idents!(temp), sp)
-                    }
+                    self.build_tensor_ty(temp, sp)
                 };
 
                 let params = if let Some(args) = param.into_inner().next() {
@@ -246,9 +431,9 @@ impl ASTBuilder {
         let param = eat!(tokens, ident, "Failed to parse function parameter", sp)?;
         let typ = eat!(tokens, ty_sig, "Failed to parse type signature", sp);
         let typ = if typ.is_err() {
-            TensorTy::Generic(vec![], sp)
+            TensorTy::Generic(vec![], None, sp)
         } else {
-            TensorTy::Generic(to_idents!(typ?.into_inner().next().unwrap()), sp)
+            self.build_tensor_ty(typ?.into_inner().next().unwrap(), sp)
         };
 
         Ok(FnDeclParam {
@@ -267,11 +452,18 @@ impl ASTBuilder {
     fn build_view_fn(&self, pair: Pair<Rule>) -> Result<ViewFn, Diag> {
         let sp = self.cspan.convert_span(&pair.clone().into_span());
         let tokens = pair.into_inner();
-        let dims = tokens.map(|p| String::from(p.as_str())).collect();
+        let dims = tokens.map(|p| self.build_dim_expr(p).unwrap()).collect();
         let span = sp;
         Ok(ViewFn { dims, span })
     }
 
+    fn build_cast_fn(&self, pair: Pair<Rule>) -> Result<CastFn, Diag> {
+        let span = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let elem_ty = build_dtype(&eat!(tokens, dtype, "Failed to parse dtype", span)?);
+        Ok(CastFn { dtype: elem_ty, span })
+    }
+
     fn build_fn_app(&self, pair: Pair<Rule>) -> Result<FnApp, Diag> {
         let sp = self.cspan.convert_span(&pair.clone().into_span());
         let mut tokens = pair.into_inner();
@@ -319,7 +511,14 @@ impl ASTBuilder {
             weights_decl_body,
             "Failed to parse `weights_decl_body`", sp
         )?.into_inner()
-            .map(|p| self.build_weights_assign(p).unwrap())
+            .map(|p| if p.as_rule() == weights_for {
+                self.build_weights_for(p)
+            } else {
+                self.build_weights_assign(p, None).map(|w_assign| vec![w_assign])
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
             .collect();
 
         Ok(Decl::WeightsDecl(WeightsDecl {
@@ -330,35 +529,49 @@ impl ASTBuilder {
         }))
     }
 
-    fn build_weights_assign(&self, body: Pair<Rule>) -> Result<WeightsAssign, Diag> {
+    /// `blocks[i]` is indexed by the variable of the enclosing `for` loop
+    fn build_weights_assign(&self, body: Pair<Rule>, loop_var: Option<&str>) -> Result<WeightsAssign, Diag> {
         let sp = self.cspan.convert_span(&body.clone().into_span());
         let mut tokens = body.into_inner();
         let name = eat!(tokens, ident, "Failed to parse ident", sp)?;
+        if tokens.clone().next().map(|p| p.as_rule()) == Some(ident) {
+            let idx = tokens.next().unwrap();
+            if Some(idx.as_str()) != loop_var {
+                let idx_sp = self.cspan.convert_span(&idx.clone().into_span());
+                return Err(Diag::SymbolNotFound(idx.as_str().to_owned(), idx_sp));
+            }
+        }
         let _assign = eat!(tokens, op_assign, "Failed to parse `=`", sp)?;
         let mod_name = eat!(tokens, cap_ident, "Failed to parse `mod_name`", sp)?;
-        let nxt = tokens.next().unwrap();
-        if nxt.as_rule() == fn_ty_sig {
-            let func = eat!(tokens, fn_app, "Failed to parse `fn_app`", sp)?;
-            let fncall = self.build_fn_app(func)?;
-            Ok(WeightsAssign {
-                name: name.as_str().to_owned(),
-                mod_name: mod_name.as_str().to_owned(),
-                fn_name: fncall.name,
-                mod_sig: Some(self.build_fn_ty_sig(nxt).expect("Cannot parse function type signature!")),
-                fn_args: fncall.args,
-                span: sp,
-            })
+        let mut nxt = tokens.next().unwrap();
+        let mod_sig = if nxt.as_rule() == fn_ty_sig {
+            let sig = self.build_fn_ty_sig(nxt).expect("Cannot parse function type signature!");
+            nxt = tokens.next().unwrap();
+            Some(sig)
         } else {
-            let fncall = self.build_fn_app(nxt)?;
-            Ok(WeightsAssign {
-                name: name.as_str().to_owned(),
-                mod_name: mod_name.as_str().to_owned(),
-                fn_name: fncall.name,
-                mod_sig: None,
-                fn_args: fncall.args,
-                span: sp,
-            })
-        }
+            None
+        };
+        let dims = if nxt.as_rule() == Rule::dim_args {
+            let vals = nxt
+                .into_inner()
+                .map(|p| self.build_dim_expr(p))
+                .collect::<Result<_,_>>()?;
+            nxt = tokens.next().unwrap();
+            vals
+        } else {
+            vec![]
+        };
+        let fncall = self.build_fn_app(nxt)?;
+        Ok(WeightsAssign {
+            name: name.as_str().to_owned(),
+            mod_name: mod_name.as_str().to_owned(),
+            fn_name: fncall.name,
+            mod_sig,
+            dim_args: dims,
+            fn_args: fncall.args,
+            range: None,
+            span: sp,
+        })
     }
 
     fn _process_level(&self, curr: Pair<Rule>) -> Term {
@@ -369,8 +582,17 @@ impl ASTBuilder {
         } else if curr.as_rule() == ident {
             let span = self.cspan.convert_span(&curr.clone().into_span());
             Term::Ident(curr.as_str().to_owned(), span)
+        } else if curr.as_rule() == index {
+            self.build_index(curr).unwrap()
         } else if curr.as_rule() == view_fn {
             Term::ViewFn(self.build_view_fn(curr).unwrap())
+        } else if curr.as_rule() == cast_fn {
+            Term::CastFn(self.build_cast_fn(curr).unwrap())
+        } else if curr.as_rule() == arith_expr {
+            self.build_arith_expr(curr).unwrap()
+        } else if curr.as_rule() == int_lit || curr.as_rule() == tuple {
+            // literal indices `(3, 14, 15) |> emb`
+            self.consume(curr).unwrap()
         } else {
             panic!("{:?}", curr.as_rule());
         }
@@ -468,6 +690,14 @@ impl ASTBuilder {
         let mut tokens = pair.into_inner();
         let mut head = eat!(tokens, node_decl_head, "Parsing `node_head` error", sp)?.into_inner();
         let node_name = eat!(head, cap_ident, "Does not have a node name", sp)?.as_str();
+        let params = if head.clone().next().map(|p| p.as_rule()) == Some(Rule::dim_params) {
+            head.next().unwrap()
+                .into_inner()
+                .map(|p| p.as_str().to_owned())
+                .collect()
+        } else {
+            vec![]
+        };
         let ty_decl = eat!(head, fn_ty_sig, "Failed to parse `fn_ty_sig`", sp)?;
         let node_body = eat!(tokens, node_decl_body, "Failed to parse `node_decl_body`", sp)?;
 
@@ -485,6 +715,7 @@ impl ASTBuilder {
 
         Ok(Decl::NodeDecl(NodeDecl {
             name: node_name.to_owned(),
+            dim_params: params,
             ty_sig: ty_signature,
             defs: macros,
             span: sp,
@@ -510,8 +741,8 @@ impl ASTBuilder {
 
         let identifier = identifier.as_str().to_owned();
 
-        let handle_lit = move |token: Pair<Rule>, id: String, sp: ByteSpan| {
-            let lit = self.consume(token)?;
+        let handle_dim = move |token: Pair<Rule>, id: String, sp: ByteSpan| {
+            let lit = self.build_dim_expr(token)?;
             Ok(AliasAssign::Dimension {
                 ident: id,
                 rhs: lit,
@@ -520,19 +751,17 @@ impl ASTBuilder {
         };
 
         let handle_ty = move |ty: Pair<Rule>, id: String, sp: ByteSpan| {
-            let ty = to_idents!(ty);
             Ok(AliasAssign::Tensor {
                 ident: id,
-                rhs: TensorTy::Generic(ty, sp),
+                rhs: self.build_tensor_ty(ty, sp),
                 span: sp,
             })
         };
 
         let tok = tokens.next().unwrap();
         match tok.as_rule() {
-            int_lit => handle_lit(tok, identifier, sp),
-            float_lit => handle_lit(tok, identifier, sp),
-            ty_ident_list => handle_ty(tok, identifier, sp),
+            dim_expr => handle_dim(tok, identifier, sp),
+            tensor_ty_sig => handle_ty(tok, identifier, sp),
             _ => unimplemented!(),
         }
     }
@@ -543,6 +772,12 @@ impl ASTBuilder {
         Ok(Term::Float(ret, span))
     }
 
+    fn build_bool_lit(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
+        let ret = pair.as_str() == "true";
+        let span = self.cspan.convert_span(&pair.into_span());
+        Ok(Term::Bool(ret, span))
+    }
+
     fn build_int_lit(&self, pair: Pair<Rule>) -> Result<Term, Diag> {
         let ret = pair.as_str().parse().unwrap();
         let span = self.cspan.convert_span(&pair.into_span());
@@ -553,16 +788,20 @@ impl ASTBuilder {
         let sp = self.cspan.convert_span(&pair.clone().into_span());
         let mut tokens = pair.into_inner();
 
-        let handle_tensor_ty = |token: Pair<Rule>| TensorTy::Generic(to_idents!(token), sp);
-        let handle_alias = |token: Pair<Rule>| TensorTy::Tensor(token.as_str().to_owned(), sp);
-        let handle = |tok: Pair<Rule>| match tok.as_rule() {
-            ty_ident_list => handle_tensor_ty(tok),
-            ident => handle_alias(tok),
-            _ => unimplemented!(),
-        };
+        let handle = |tok: Pair<Rule>| self.build_tensor_ty(tok, sp);
 
         let tok = tokens.next().unwrap();
-        let from_ty = handle(tok);
+        let from_ty = if tok.as_rule() == named_tensor_tys {
+            tok.into_inner()
+                .map(|named| {
+                    let mut named = named.into_inner();
+                    let name = named.next().unwrap().as_str().to_owned();
+                    (Some(name), handle(named.next().unwrap()))
+                })
+                .collect()
+        } else {
+            vec![(None, handle(tok))]
+        };
 
         let tok = tokens.next().unwrap();
         let to_ty = handle(tok);
@@ -573,20 +812,41 @@ impl ASTBuilder {
         })
     }
 
+    /// `[?,10]`, `[?]: i64`, `LABELS` or `([?,10], [?,1])`
+    fn build_tensor_ty(&self, tok: Pair<Rule>, sp: ByteSpan) -> TensorTy {
+        match tok.as_rule() {
+            tensor_ty_sig => {
+                let mut tokens = tok.into_inner();
+                let dims = self.build_ty_dims(tokens.next().unwrap());
+                let elem_ty = tokens.next().map(|t| build_dtype(&t));
+                TensorTy::Generic(dims, elem_ty, sp)
+            }
+            ident => TensorTy::Tensor(tok.as_str().to_owned(), sp),
+            tensor_tys => TensorTy::Tuple(
+                tok.into_inner().map(|t| self.build_tensor_ty(t, sp)).collect(),
+                sp,
+            ),
+            _ => unimplemented!(),
+        }
+    }
+
     fn build_use_stmt(&self, pair: Pair<Rule>) -> Result<Decl, Diag> {
         let sp = self.cspan.convert_span(&pair.clone().into_span());
         let mut tokens = pair.into_inner();
         let _use_lit = eat!(tokens, use_lit, "Parsing `use` error", sp)?;
-        let module_name = eat!(tokens, ident, "module name not defined", sp)?.as_str();
+        let module_name = eat!(tokens, use_path, "module name not defined", sp)?
+            .into_inner()
+            .map(|tok| tok.as_str())
+            .collect::<Vec<_>>()
+            .join("::");
         let imported = eat!(tokens, "no imported modules", sp)?;
 
-        let mut imported_tokens = vec![];
-        match imported.as_rule() {
-            Rule::ident_list => imported
+        let imported_tokens = match imported.as_rule() {
+            Rule::use_name_list => imported
                 .into_inner()
-                .map(|tok| imported_tokens.push(tok.as_str().to_owned()))
-                .collect(),
-            Rule::ident => imported_tokens.push(imported.as_str().to_owned()),
+                .map(|tok| self.build_use_name(tok))
+                .collect::<Result<_,_>>()?,
+            Rule::use_name => vec![self.build_use_name(imported)?],
             _ => unexpected_token(&imported),
         };
 
@@ -597,9 +857,48 @@ impl ASTBuilder {
         }))
     }
 
+    /// `Linear` or `Linear as L`
+    fn build_use_name(&self, pair: Pair<Rule>) -> Result<(String, String), Diag> {
+        let sp = self.cspan.convert_span(&pair.clone().into_span());
+        let mut tokens = pair.into_inner();
+        let name = eat!(tokens, ident, "Failed to parse imported name", sp)?.as_str();
+        let alias = match tokens.next() {
+            Some(_as) => eat!(tokens, ident, "Failed to parse alias after `as`", sp)?.as_str(),
+            None => name,
+        };
+        Ok((name.to_owned(), alias.to_owned()))
+    }
+
 }
 
 fn unexpected_token(pair: &Pair<Rule>) -> ! {
     let message = format!("Unexpected token: {:#}", pair);
     panic!(message);
 }
+
+/// whether the statement is an `if` with an `else` branch
+fn is_if_else(term: &Term) -> bool {
+    match term {
+        Term::Stmt(box Term::List(ref vals), _) if vals.len() == 1 => match vals[0] {
+            Term::Conditional { ref els, .. } => **els != Term::None,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn build_dtype(pair: &Pair<Rule>) -> DType {
+    DType::from_str(pair.as_str()).unwrap_or_else(|| unexpected_token(pair))
+}
+
+fn build_op(pair: &Pair<Rule>) -> Op {
+    match pair.as_rule() {
+        op_expo => Op::Expo,
+        op_mult => Op::Mult,
+        op_div => Op::Div,
+        op_mod => Op::Mod,
+        op_add => Op::Add,
+        op_sub => Op::Sub,
+        _ => unexpected_token(pair),
+    }
+}
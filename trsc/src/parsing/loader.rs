@@ -0,0 +1,92 @@
+/// Resolves `use` of other .trs files.
+///
+/// `use models::encoder::Encoder;` reads `models/encoder.trs` relative to the
+/// directory of the entry file. Every file is parsed once and its declarations
+/// are spliced in front of the first file that uses it, so nodes, dims and
+/// tsr aliases are already in global scope when the `use` is annotated.
+use core::Core;
+use errors::{Diag, Emitter};
+use parsing::ast_builder::ASTBuilder;
+use parsing::term::{Decl, Term};
+use span::CSpan;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+pub struct Loader {
+    emitter: Rc<RefCell<Emitter>>,
+    core: Rc<RefCell<Core>>,
+    root: PathBuf,
+    loaded: RefCell<BTreeSet<PathBuf>>,
+}
+
+impl Loader {
+    pub fn new(emitter: Rc<RefCell<Emitter>>, core: Rc<RefCell<Core>>, entry: &Path) -> Self {
+        let root = entry
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(PathBuf::new);
+        Self {
+            emitter,
+            core,
+            root,
+            loaded: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// parses the entry file and every file it uses into one program
+    pub fn load(&self, fname: &str, src: &str) -> Option<Term> {
+        self.loaded.borrow_mut().insert(canonical(Path::new(fname)));
+        self.load_src(fname, src).map(Term::Program)
+    }
+
+    fn load_src(&self, fname: &str, src: &str) -> Option<Vec<Decl>> {
+        let span = self.emitter.borrow_mut().add_file(fname.to_owned(), src.to_owned());
+        let builder = ASTBuilder::new(Rc::clone(&self.emitter), CSpan::new(span));
+        let decls = match builder.parse_str(src)? {
+            Term::Program(decls) => decls,
+            _ => unreachable!(),
+        };
+
+        let mut program = vec![];
+        for decl in &decls {
+            let stmt = match decl {
+                Decl::UseStmt(stmt) => stmt,
+                _ => continue,
+            };
+            if self.core.borrow().has_path(&stmt.mod_name) {
+                continue;
+            }
+            let path = self.path_of(&stmt.mod_name);
+            if !self.loaded.borrow_mut().insert(canonical(&path)) {
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(imported) => {
+                    let name = path.to_string_lossy().into_owned();
+                    program.extend(self.load_src(&name, &imported)?);
+                }
+                Err(_) => self.emitter.borrow_mut()
+                    .add(Diag::ImportError(stmt.mod_name.to_owned(), stmt.span)),
+            }
+        }
+        program.extend(decls);
+        Some(program)
+    }
+
+    /// `models::encoder` => `<root>/models/encoder.trs`
+    fn path_of(&self, mod_path: &str) -> PathBuf {
+        let mut path = self.root.clone();
+        for seg in mod_path.split("::") {
+            path.push(seg);
+        }
+        path.set_extension("trs");
+        path
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
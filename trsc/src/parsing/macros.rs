@@ -47,13 +47,3 @@ macro_rules! eat {
             })
     };
 }
-
-macro_rules! to_idents {
-    ($ident_list:expr) => {
-        $ident_list
-            .into_inner()
-            .map(|id| id.as_str())
-            .map(String::from)
-            .collect()
-    };
-}
\ No newline at end of file
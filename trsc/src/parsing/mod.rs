@@ -1,5 +1,6 @@
 #[macro_use]
 mod macros;
 pub mod ast_builder;
+pub mod loader;
 pub mod grammar;
 pub mod term;
@@ -13,9 +13,12 @@ pub enum Term {
     Program(Vec<Decl>),
     Integer(i64, ByteSpan),
     Float(f64, ByteSpan),
+    Bool(bool, ByteSpan),
     List(Vec<Term>),
     Ident(String, ByteSpan),
     ViewFn(ViewFn),
+    /// `to(i64)` casts a tensor to another element type
+    CastFn(CastFn),
     FieldAccess(FieldAccess),
     FnApp(FnApp),
     Block {
@@ -27,6 +30,30 @@ pub enum Term {
     Stmt(Box<Term>, ByteSpan),
     Pipes(Vec<Term>),
     Tuple(Vec<Term>, ByteSpan),
+    BinOp(Op, Box<Term>, Box<Term>, ByteSpan),
+    /// `if cond { .. } else { .. }`, `else if` is nested in `els`
+    Conditional {
+        cond: Expression,
+        then: Expression,
+        els: Expression,
+        span: ByteSpan,
+    },
+    /// `let h = expr;`
+    Let(String, Expression, ByteSpan),
+    /// `let (mu, logvar) = expr;`
+    LetTuple(Vec<Pattern>, Expression, ByteSpan),
+    /// `h = expr;` to an existing variable
+    Assign(String, Expression, ByteSpan),
+    /// `blocks[i]`
+    Index(String, Expression, ByteSpan),
+    /// `for i in 0..N { .. }`
+    ForLoop {
+        var: String,
+        from: Expression,
+        to: Expression,
+        body: Expression,
+        span: ByteSpan,
+    },
 }
 
 // impl Term {
@@ -62,6 +89,23 @@ pub enum Term {
 //     }
 // }
 
+/// the names bound by `let`, tuples may nest as in `(out, (h, c))`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    Ident(String),
+    Tuple(Vec<Pattern>),
+}
+
+impl Pattern {
+    /// every bound name from left to right
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            Pattern::Ident(name) => vec![name.to_owned()],
+            Pattern::Tuple(items) => items.iter().flat_map(|p| p.names()).collect(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Decl {
     NodeDecl(NodeDecl),
@@ -74,13 +118,16 @@ pub enum Decl {
 #[derive(Debug, PartialEq, Clone)]
 pub struct UseStmt {
     pub mod_name: String,
-    pub imported_names: Vec<String>,
+    /// (name, alias) pairs, alias is the name itself without `as`
+    pub imported_names: Vec<(String, String)>,
     pub span: ByteSpan,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct NodeDecl {
     pub name: String,
+    /// dimension parameters of a generic node
+    pub dim_params: Vec<String>,
     pub ty_sig: FnTySig,
     pub defs: Vec<AliasAssign>,
     pub span: ByteSpan,
@@ -137,13 +184,18 @@ pub struct WeightsAssign {
     pub mod_name: String,
     pub fn_name: String,
     pub mod_sig: Option<FnTySig>,
+    /// dimensions supplied to a generic node
+    pub dim_args: Vec<Term>,
     pub fn_args: Vec<FnAppArg>,
+    /// bounds of the `for` loop it is declared in, one copy per iteration
+    pub range: Option<(Term, Term)>,
     pub span: ByteSpan,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FnTySig {
-    pub from: TensorTy,
+    /// inputs, only named if there are several: `<(q: [..], k: [..]) -> [..]>`
+    pub from: Vec<(Option<String>, TensorTy)>,
     pub to: TensorTy,
 }
 
@@ -173,12 +225,20 @@ pub enum AliasAssign {
 #[derive(Debug, PartialEq, Clone)]
 pub enum TensorTy {
     Tensor(String, ByteSpan),
-    Generic(Vec<String>, ByteSpan),
+    /// `[?, 10]` or `[?, 10]: i64`, unannotated tensors hold `f32`
+    Generic(Vec<Term>, Option<DType>, ByteSpan),
+    Tuple(Vec<TensorTy>, ByteSpan),
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ViewFn {
-    pub dims: Vec<String>,
+    pub dims: Vec<Term>,
+    pub span: ByteSpan,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CastFn {
+    pub dtype: DType,
     pub span: ByteSpan,
 }
 
@@ -221,26 +281,84 @@ impl Display for Term {
     }
 }
 
-// #[derive(Debug, PartialEq, Clone)]
-// pub enum Op {
-//   Expo,
-//   Mult,
-//   Div,
-//   Mod,
-//   Add,
-//   Sub,
-//   ShL,
-//   ShR,
-//   BAnd,
-//   BOr,
-//   BXor,
-//   Lt,
-//   LtE,
-//   Gt,
-//   GtE,
-//   Eq,
-//   NotEq,
-//   And,
-//   Or,
-//   Assign,
-// }
+/// element type of a tensor
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub enum DType {
+    F32,
+    F16,
+    I64,
+    Bool,
+}
+
+impl DType {
+    pub fn from_str(s: &str) -> Option<DType> {
+        use self::DType::*;
+        match s {
+            "f32" => Some(F32),
+            "f16" => Some(F16),
+            "i64" => Some(I64),
+            "bool" => Some(Bool),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        use self::DType::*;
+        match self {
+            F32 => "f32",
+            F16 => "f16",
+            I64 => "i64",
+            Bool => "bool",
+        }
+    }
+
+    /// name of the dtype in pytorch
+    pub fn as_torch(&self) -> &'static str {
+        use self::DType::*;
+        match self {
+            F32 => "torch.float32",
+            F16 => "torch.float16",
+            I64 => "torch.int64",
+            Bool => "torch.uint8",
+        }
+    }
+}
+
+/// binary operators allowed in expressions
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub enum Op {
+    Expo,
+    Mult,
+    Div,
+    Mod,
+    Add,
+    Sub,
+}
+
+impl Op {
+    pub fn as_str(&self) -> &'static str {
+        use self::Op::*;
+        match self {
+            Expo => "**",
+            Mult => "*",
+            Div => "/",
+            Mod => "%",
+            Add => "+",
+            Sub => "-",
+        }
+    }
+
+    /// integer evaluation used for dimension arithmetic,
+    /// returns None on overflow or division by zero
+    pub fn eval(&self, a: i64, b: i64) -> Option<i64> {
+        use self::Op::*;
+        match self {
+            Expo => if b < 0 { None } else { (0..b).try_fold(1i64, |acc, _| acc.checked_mul(a)) },
+            Mult => a.checked_mul(b),
+            Div => a.checked_div(b),
+            Mod => a.checked_rem(b),
+            Add => a.checked_add(b),
+            Sub => a.checked_sub(b),
+        }
+    }
+}
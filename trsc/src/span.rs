@@ -19,6 +19,16 @@ impl CSpan {
         Span::new(ByteIndex(0), ByteIndex(0))
     }
 
+    /// whether `sp` is the placeholder of a type built without a source location
+    pub fn is_fresh(sp: &ByteSpan) -> bool {
+        *sp == CSpan::fresh_span()
+    }
+
+    /// an empty span at a byte offset into this file
+    pub fn convert_pos(&self, pos: usize) -> ByteSpan {
+        self.sp.subspan(ByteOffset(pos as i64), ByteOffset(pos as i64))
+    }
+
     pub fn convert_span(&self, sp: &PestSpan) -> ByteSpan {
         // Span::new(ByteIndex(sp.start() as u32 + 1), ByteIndex(sp.end() as u32 + 1))
         self.sp.subspan(ByteOffset(sp.start() as i64), ByteOffset(sp.end() as i64))
@@ -1,6 +1,6 @@
 use codespan::ByteSpan;
 use parsing::term::{Decl, FieldAccess, FnApp, FnAppArg, FnDecl, FnDeclParam, FnTySig, TensorTy,
-                   Term, ViewFn, WeightsAssign};
+                   Pattern, Term, ViewFn, CastFn, WeightsAssign};
 use span::CSpan;
 use typing::type_env::{Alias, ModName, TypeEnv};
 use typing::typed_term::ArgsVecInto;
@@ -16,6 +16,8 @@ use errors::{Diag, Emitter};
 pub struct Annotator {
     pub emitter: Rc<RefCell<Emitter>>,
     pub tenv: Rc<RefCell<TypeEnv>>,
+    /// variable and bounds of the enclosing `for` loops, innermost last
+    loops: RefCell<Vec<(String, i64, i64)>>,
 }
 
 impl Annotator {
@@ -23,6 +25,7 @@ impl Annotator {
         Self {
             emitter,
             tenv,
+            loops: RefCell::new(vec![]),
         }
     }
 
@@ -73,19 +76,22 @@ impl Annotator {
 
             Integer(i, s) => TyInteger(Type::INT(*s), *i, *s),
             Float(i, s) => TyFloat(Type::FLOAT(*s), *i, *s),
+            Bool(b, s) => TyBool(Type::BOOL(*s), *b, *s),
             Block {
                 ref stmts,
                 ref ret,
                 ref span,
             } => {
                 let module = self.tenv.borrow().module();
+                let depth = self.tenv.borrow().scope_depth(&module);
                 self.tenv.borrow_mut().push_scope(&module);
                 let ret = TyBlock {
                     stmts: Box::new(self.annotate(&stmts)),
                     ret: Box::new(self.annotate(&ret)),
                     span: *span,
                 };
-                self.tenv.borrow_mut().pop_scope(&module);
+                // also exits the scopes opened by `let` in this block
+                self.tenv.borrow_mut().pop_scopes_to(&module, depth);
                 ret
             }
             List(ref stmts) => TyList(stmts.iter().map(|s| self.annotate(&s)).collect()),
@@ -97,10 +103,149 @@ impl Annotator {
             None => TyNone,
             Pipes(ref pipes) => self.annotate_pipes(pipes),
             Tuple(ref terms, ref s) => self.annotate_tuples(terms, s),
+            BinOp(ref op, ref lhs, ref rhs, ref span) => {
+                let ty = self.tenv.borrow_mut().fresh_var(*span);
+                TyBinOp(
+                    ty,
+                    *op,
+                    box self.annotate(&lhs),
+                    box self.annotate(&rhs),
+                    *span,
+                )
+            }
+            Let(ref name, ref rhs, ref span) => {
+                let (rhs, mut bindings) = self.annotate_let(&[name.to_owned()], rhs, span);
+                let (ty, alias) = bindings.remove(0);
+                TyLet(ty, alias, box rhs, *span)
+            }
+            LetTuple(ref items, ref rhs, ref span) => {
+                let names = items.iter().flat_map(|p| p.names()).collect::<Vec<_>>();
+                let (rhs, bindings) = self.annotate_let(&names, rhs, span);
+                // nested patterns bind nested tuples
+                let mut tys = bindings.into_iter().map(|(ty, _)| ty);
+                let tys = items.iter().map(|p| pattern_ty(p, &mut tys, span)).collect();
+                TyLetTuple(Type::Tuple(tys, *span), items.clone(), box rhs, *span)
+            }
+            Assign(ref name, ref rhs, ref span) => {
+                let rhs = self.annotate(&rhs);
+                let alias = Alias::Variable(name.to_owned());
+                let ty = self.tenv.borrow()
+                    .resolve_type(&module, &alias)
+                    .unwrap_or_else(|| {
+                        let mut em = self.emitter.borrow_mut();
+                        em.add(Diag::SymbolNotFound(name.to_owned(), *span));
+                        em.print_errs();
+                        exit(-1);
+                    });
+                TyAssign(ty, alias, box rhs, *span)
+            }
+            ForLoop {
+                ref var,
+                ref from,
+                ref to,
+                ref body,
+                ref span,
+            } => {
+                let (from, to) = self.annotate_loop_range(&from, &to, span);
+                self.loops.borrow_mut().push((var.to_owned(), from, to));
+                let var = Alias::Variable(var.to_owned());
+                self.tenv.borrow_mut().push_scope(&module);
+                self.tenv.borrow_mut()
+                    .add_type(&module, &var, Type::INT(*span))
+                    .unwrap();
+                let body = box self.annotate(&body);
+                self.tenv.borrow_mut().pop_scope(&module);
+                self.loops.borrow_mut().pop();
+                TyForLoop { var, from, to, body, span: *span }
+            }
+            Conditional {
+                ref cond,
+                ref then,
+                ref els,
+                ref span,
+            } => {
+                let ty = self.tenv.borrow_mut().fresh_var(*span);
+                TyConditional {
+                    ty,
+                    cond: box self.annotate(&cond),
+                    then: box self.annotate(&then),
+                    els: box self.annotate(&els),
+                    span: *span,
+                }
+            }
             _ => unimplemented!(),
         }
     }
 
+    /// `let` opens a new scope that lasts until the end of the enclosing
+    /// block so a binding may shadow an earlier variable of the same name.
+    /// Weights and imported modules cannot be shadowed.
+    fn annotate_let(&self, names: &[String], rhs: &Term, span: &ByteSpan) -> (TyTerm, Vec<(Type, Alias)>) {
+        let module = self.tenv.borrow().module();
+        // annotate rhs first so `let x = x |> f;` refers to the outer `x`
+        let rhs = self.annotate(rhs);
+        let bindings = names
+            .iter()
+            .map(|name| {
+                let alias = Alias::Variable(name.to_owned());
+                let prev = self.tenv.borrow()
+                    .resolve_type(&module, &alias)
+                    .or_else(|| self.tenv.borrow().resolve_type(&ModName::Global, &alias));
+                let ty = self.tenv.borrow_mut().fresh_var(*span);
+                match prev {
+                    Some(ref prev @ Type::Module(..)) | Some(ref prev @ Type::UnresolvedModuleFun(..)) => {
+                        let mut em = self.emitter.borrow_mut();
+                        em.add(Diag::DuplicateVarInScope(name.to_owned(), prev.clone(), ty));
+                        em.print_errs();
+                        exit(-1);
+                    }
+                    _ => (),
+                }
+                (ty, alias)
+            })
+            .collect::<Vec<_>>();
+        self.tenv.borrow_mut().push_scope(&module);
+        for (ty, alias) in &bindings {
+            self.tenv.borrow_mut()
+                .add_type(&module, alias, ty.clone())
+                .unwrap_or_else(|e| {
+                    let mut em = self.emitter.borrow_mut();
+                    em.add(e);
+                    em.print_errs();
+                    exit(-1);
+                });
+        }
+        (rhs, bindings)
+    }
+
+    /// `from..to` counts up, `from == to` runs the body zero times
+    fn annotate_loop_range(&self, from: &Term, to: &Term, span: &ByteSpan) -> (i64, i64) {
+        let from = self.annotate_loop_bound(from, span);
+        let to = self.annotate_loop_bound(to, span);
+        if to < from {
+            let mut em = self.emitter.borrow_mut();
+            em.add(Diag::DescendingLoop(from, to, *span));
+            em.print_errs();
+            exit(-1);
+        }
+        (from, to)
+    }
+
+    /// loop bounds are dimension expressions known at compile time
+    fn annotate_loop_bound(&self, bound: &Term, span: &ByteSpan) -> i64 {
+        let module = self.tenv.borrow().module();
+        let ty = self.tenv.borrow_mut().eval_dim(&module, bound, span);
+        match ty {
+            Type::ResolvedDim(n, _) => n,
+            _ => {
+                let mut em = self.emitter.borrow_mut();
+                em.add(Diag::UnboundedLoop(ty, *span));
+                em.print_errs();
+                exit(-1);
+            }
+        }
+    }
+
     fn annotate_tuples(&self, tup: &[Term], s: &ByteSpan) -> TyTerm {
         let (vs, tys)  = tup.iter().map(|i| {
             let tyterm = self.annotate(i);
@@ -120,32 +265,24 @@ impl Annotator {
         let mut term0 = self.annotate(p0);
 
         for t in it {
+            let span = term0.span();
             let prev_arg = TyFnAppArg {
                 name: Some(String::from("x")),
-                arg: Box::new(term0.clone()),
-                span: term0.span(),
+                arg: Box::new(term0),
+                span,
             };
             let t = match t {
                 // this may be `fc1`
                 Term::Ident(ref id, ref span) => {
-                    let arg_ty = self.tenv.borrow_mut().fresh_var(*span);
-                    let ret_ty = self.tenv.borrow_mut().fresh_var(*span);
-                    TyTerm::TyFnApp(box TyFnApp {
-                        mod_name: Some(
-                            self.tenv.borrow().resolve_type(&module, &Alias::Variable(id.clone()))
-                                .or_else(|| {
-                                    self.tenv.borrow().resolve_type(&ModName::Global, &Alias::Variable(id.clone()))
-                                })
-                                .unwrap()
-                                .as_string(),
-                        ),
-                        orig_name: Some(id.to_owned()),
-                        name: Alias::Function("forward".to_owned()),
-                        arg_ty,
-                        args: vec![prev_arg],
-                        ret_ty,
-                        span: *span,
-                    })
+                    self.check_module_index(id, None, span);
+                    TyTerm::TyFnApp(box self.annotate_forward(id, prev_arg, span))
+                }
+                // or `blocks[i]`
+                Term::Index(ref id, ref idx, ref span) => {
+                    self.check_module_index(id, Some(idx), span);
+                    let mut fn_app = self.annotate_forward(id, prev_arg, span);
+                    fn_app.index = Some(box self.annotate(&idx));
+                    TyTerm::TyFnApp(box fn_app)
                 }
                 Term::FnApp(ref fn_app) => {
                     let mut typed_fn_app = self.annotate_fn_app(&fn_app);
@@ -159,7 +296,7 @@ impl Annotator {
                         );
                         typed_fn_app.name = Alias::Function("forward".to_owned());
                     }
-                    typed_fn_app.extend_arg(&prev_arg);
+                    typed_fn_app.extend_arg(prev_arg);
                     TyTerm::TyFnApp(box typed_fn_app)
                 }
                 Term::FieldAccess(ref f_a) => {
@@ -167,41 +304,124 @@ impl Annotator {
                     match typed_f_a {
                         TyTerm::TyFnApp(ref fn_app) => {
                             let mut fn_app = fn_app.clone();
-                            fn_app.extend_arg(&prev_arg);
+                            fn_app.extend_arg(prev_arg);
                             TyTerm::TyFnApp(fn_app)
                         }
                         _ => panic!("Error: for field access in a pipeline, use parenthesis: f()"),
                     }
                 }
-                Term::ViewFn(ref v_f) => TyTerm::TyFnApp(box self.annotate_view_fn(&v_f, &prev_arg)),
+                Term::ViewFn(ref v_f) => TyTerm::TyFnApp(box self.annotate_view_fn(&v_f, prev_arg)),
+                Term::CastFn(ref c_f) => TyTerm::TyFnApp(box self.annotate_cast_fn(&c_f, prev_arg)),
                 _ => unimplemented!(),
             };
-            term0 = t.clone();
+            term0 = t;
         }
 
         term0
     }
 
-    fn annotate_view_fn(&self, v_fn: &ViewFn, arg: &TyFnAppArg) -> TyFnApp {
+    /// a list of modules is called through an index within its length,
+    /// either a literal or the variable of an enclosing `for` loop
+    fn check_module_index(&self, id: &str, idx: Option<&Term>, span: &ByteSpan) {
+        let module = self.tenv.borrow().module();
+        let count = match self.tenv.borrow().resolve_type(&module, &Alias::Variable(id.to_owned())) {
+            Some(Type::Module(_, _, count, _)) => count,
+            _ => None,
+        };
+        let diag = match (count, idx) {
+            (None, None) => None,
+            (Some(n), None) => Some(Diag::UnindexedModuleList(id.to_owned(), n, *span)),
+            (None, Some(_)) => Some(Diag::NotAModuleList(id.to_owned(), *span)),
+            (Some(n), Some(idx)) => {
+                let range = match idx {
+                    Term::Integer(i, _) => Some((*i, *i + 1)),
+                    Term::Ident(ref var, _) => self.loops.borrow()
+                        .iter()
+                        .rev()
+                        .find(|(v, _, _)| v == var)
+                        .map(|&(_, from, to)| (from, to)),
+                    _ => None,
+                };
+                match range {
+                    Some((from, _)) if from < 0 =>
+                        Some(Diag::IndexOutOfRange(format!("{}[{}]", id, from), 0, n, *span)),
+                    Some((from, to)) if from < to && to > n =>
+                        Some(Diag::IndexOutOfRange(format!("{}[{}]", id, to - 1), 0, n, *span)),
+                    _ => None,
+                }
+            }
+        };
+        if let Some(diag) = diag {
+            self.emitter.borrow_mut().add(diag);
+        }
+    }
+
+    /// the forward function of a module in a pipeline
+    fn annotate_forward(&self, id: &str, prev_arg: TyFnAppArg, span: &ByteSpan) -> TyFnApp {
+        let module = self.tenv.borrow().module();
+        let arg_ty = self.tenv.borrow_mut().fresh_var(*span);
+        let ret_ty = self.tenv.borrow_mut().fresh_var(*span);
+        TyFnApp {
+            mod_name: Some(
+                self.tenv.borrow().resolve_type(&module, &Alias::Variable(id.to_owned()))
+                    .or_else(|| {
+                        self.tenv.borrow().resolve_type(&ModName::Global, &Alias::Variable(id.to_owned()))
+                    })
+                    .unwrap()
+                    .as_string(),
+            ),
+            orig_name: Some(id.to_owned()),
+            name: Alias::Function("forward".to_owned()),
+            arg_ty,
+            args: vec![prev_arg],
+            ret_ty,
+            index: None,
+            span: *span,
+        }
+    }
+
+    fn annotate_view_fn(&self, v_fn: &ViewFn, arg: TyFnAppArg) -> TyFnApp {
         let module = self.tenv.borrow().module();
-        let tsr = self.tenv.borrow_mut().create_tensor(&module, &v_fn.dims, &v_fn.span);
+        // the element type is carried over from the input
+        let dtype = self.tenv.borrow_mut().fresh_var(v_fn.span);
+        let tsr = self.tenv.borrow_mut().create_tensor(&module, &v_fn.dims, dtype, &v_fn.span);
         TyFnApp {
             mod_name: Some("view".to_string()),
             orig_name: None,
             name: Alias::Function("forward".to_owned()),
             arg_ty: args!(arg!("x", arg.arg.ty())),
             ret_ty: tsr.clone(),
-            args: vec![arg.clone()],
+            args: vec![arg],
+            index: None,
             span: v_fn.span,
         }
     }
 
+    /// `to(i64)` keeps the shape of its input
+    fn annotate_cast_fn(&self, c_fn: &CastFn, arg: TyFnAppArg) -> TyFnApp {
+        let dims = self.tenv.borrow_mut().fresh_dims(c_fn.span);
+        let tsr = Type::TSR(vec![dims], box Type::DTYPE(c_fn.dtype, c_fn.span), c_fn.span);
+        TyFnApp {
+            mod_name: Some("to".to_string()),
+            orig_name: None,
+            name: Alias::Function("forward".to_owned()),
+            arg_ty: args!(arg!("x", arg.arg.ty())),
+            ret_ty: tsr,
+            args: vec![arg],
+            index: None,
+            span: c_fn.span,
+        }
+    }
+
     fn annotate_decl(&self, decl: &Decl) -> Result<TyDecl, Diag> {
         use self::Decl::*;
         let ret = match decl {
             NodeDecl(ref decl) => {
                 let module = ModName::Named(decl.name.to_owned());
                 self.tenv.borrow_mut().set_module(module.clone());
+                self.tenv.borrow_mut()
+                    .add_dim_params(&module, &decl.dim_params, decl.span)
+                    .unwrap_or_else(|e| self.emitter.borrow_mut().add(e));
                 let assigns = &decl.defs;
                 for a in assigns {
                     self.tenv.borrow_mut()
@@ -211,9 +431,11 @@ impl Annotator {
 
                 self.tenv.borrow_mut().upsert_module(&module);
                 // if some dimension alias are not imported, create them
-                self.tenv.borrow_mut()
-                    .import_top_level_ty_sig(&module, &decl.ty_sig.from)
-                    .unwrap_or_else(|e| self.emitter.borrow_mut().add(e));
+                for (_, from) in &decl.ty_sig.from {
+                    self.tenv.borrow_mut()
+                        .import_top_level_ty_sig(&module, from)
+                        .unwrap_or_else(|e| self.emitter.borrow_mut().add(e));
+                }
                 self.tenv.borrow_mut()
                     .import_top_level_ty_sig(&module, &decl.ty_sig.to)
                     .unwrap_or_else(|e| self.emitter.borrow_mut().add(e));
@@ -226,6 +448,7 @@ impl Annotator {
                 let mod_ty_sig = Type::Module(
                     decl.name.clone(),
                     Some(box ty_sig.clone()),
+                    None,
                     decl.span,
                 );
 
@@ -279,12 +502,25 @@ impl Annotator {
                 // in global scope
                 // import names into scope
                 // also import module and its associated functions
-                for name in &decl.imported_names {
-                    let ty = Type::Module(name.to_string(), None, decl.span);
+                // an alias points at the same module as the original name
+                for (name, alias) in &decl.imported_names {
+                    if !self.tenv.borrow().is_core_path(&decl.mod_name) {
+                        self.annotate_file_import(name, alias, &decl.span);
+                        continue;
+                    }
+                    // already imported by another file
+                    let imported = self.tenv.borrow()
+                        .resolve_type(&ModName::Global, &Alias::Variable(alias.to_string()));
+                    if let Some(Type::Module(ref imported_name, None, ..)) = imported {
+                        if imported_name == name {
+                            continue;
+                        }
+                    }
+                    let ty = Type::Module(name.to_string(), None, None, decl.span);
                     self.tenv.borrow_mut()
                         .add_type(
                             &ModName::Global,
-                            &Alias::Variable(name.to_string()),
+                            &Alias::Variable(alias.to_string()),
                             ty)
                         .unwrap_or_else(|e|self.emitter.borrow_mut().add(e));
                     let import_result = self.tenv.borrow_mut().import_module(&decl.mod_name, &name);
@@ -315,21 +551,55 @@ impl Annotator {
         Ok(ret)
     }
 
+    /// names from a .trs file are already declared in global scope
+    /// by the loader, only the alias needs to be added
+    fn annotate_file_import(&self, name: &str, alias: &str, span: &ByteSpan) {
+        let ty = self.tenv.borrow()
+            .resolve_type(&ModName::Global, &Alias::Variable(name.to_owned()));
+        match ty {
+            Some(ty) => if name != alias {
+                self.tenv.borrow_mut()
+                    .add_type(&ModName::Global, &Alias::Variable(alias.to_owned()), ty)
+                    .unwrap_or_else(|e| self.emitter.borrow_mut().add(e));
+            },
+            None => self.emitter.borrow_mut()
+                .add(Diag::ImportError(name.to_owned(), *span)),
+        }
+    }
+
+    /// a single input is a tensor, named inputs are `FnArgs`
     fn annotate_fn_ty_sig(&self, modname: String, name: String, sig: &FnTySig, span: &ByteSpan) -> Result<Type, Diag> {
+        let from = match sig.from.as_slice() {
+            [(None, ref ty)] => self.annotate_tensor_ty_sig(ty, span)?,
+            params => Type::FnArgs(
+                params
+                    .iter()
+                    .map(|(name, ty)| Ok(Type::FnArg(
+                        name.clone(),
+                        box self.annotate_tensor_ty_sig(ty, span)?,
+                        *span,
+                    )))
+                    .collect::<Result<_, Diag>>()?,
+                *span,
+            ),
+        };
         Ok(Type::FUN(
             modname,
             name,
-            Box::new(self.annotate_tensor_ty_sig(&sig.from, span)?),
+            Box::new(from),
             Box::new(self.annotate_tensor_ty_sig(&sig.to, span)?),
             *span,
         ))
     }
 
-    fn annotate_tensor_ty_sig(&self, sig: &TensorTy, _span: &ByteSpan) -> Result<Type, Diag> {
+    fn annotate_tensor_ty_sig(&self, sig: &TensorTy, span: &ByteSpan) -> Result<Type, Diag> {
         use self::TensorTy::*;
         let module = self.tenv.borrow().module();
         match sig {
-            Generic(ref dims, ref sp) => Ok(self.tenv.borrow_mut().create_tensor(&module, dims, sp)),
+            Generic(ref dims, ref dtype, ref sp) => {
+                let dtype = self.tenv.borrow().resolve_dtype(*dtype, sp);
+                Ok(self.tenv.borrow_mut().create_tensor(&module, dims, dtype, sp))
+            }
             Tensor(ref als, ref sp) => {
                 let ty = self.tenv.borrow()
                     .resolve_type(&module, &Alias::Variable(als.clone()))
@@ -342,30 +612,69 @@ impl Annotator {
                         Err(Diag::SymbolNotFound(als.clone(), *sp)),
                 }
             }
+            Tuple(ref tys, ref sp) => Ok(Type::Tuple(
+                tys.iter()
+                    .map(|t| self.annotate_tensor_ty_sig(t, span))
+                    .collect::<Result<_,_>>()?,
+                *sp,
+            )),
         }
     }
 
     fn annotate_weights_assign(&self, w_assign: &WeightsAssign) -> TyWeightsAssign {
         let name = w_assign.name.clone();
+        // `L::new()` after `use lin::Linear as L;` is a `Linear`
+        let global_ty = self.tenv.borrow()
+            .resolve_type(&ModName::Global, &Alias::Variable(w_assign.mod_name.clone()));
+        let mod_name = global_ty
+            .as_ref()
+            .map(|ty| ty.as_string())
+            .unwrap_or_else(|| w_assign.mod_name.clone());
+        let module = self.tenv.borrow().module();
+        let dim_args = self.annotate_dim_args(&mod_name, &w_assign.dim_args, &w_assign.span);
+        // a user-defined node carries its declared signature as forward type
+        let node_fn_ty = match global_ty {
+            Some(Type::Module(_, Some(fn_ty), ..)) => {
+                let node = ModName::Named(mod_name.to_owned());
+                if self.tenv.borrow().is_generic(&node) {
+                    let dims = w_assign.dim_args
+                        .iter()
+                        .map(|d| self.tenv.borrow_mut().eval_dim(&module, d, &w_assign.span))
+                        .collect();
+                    Some(box self.tenv.borrow_mut().instantiate(&node, &fn_ty, dims))
+                } else {
+                    Some(fn_ty)
+                }
+            }
+            _ => None,
+        };
         let fn_ty = w_assign
             .clone()
             .mod_sig
             .map(|sig|
                 box self.annotate_fn_ty_sig(
-                    w_assign.mod_name.to_owned(),
+                    mod_name.to_owned(),
                     "forward".to_owned(),
                     &sig,
                     &w_assign.span
                 ).unwrap()
-            );
+            )
+            .or(node_fn_ty);
+
+        let count = w_assign.range
+            .as_ref()
+            .map(|(from, to)| {
+                let (from, to) = self.annotate_loop_range(from, to, &w_assign.span);
+                to - from
+            });
 
-        let module = self.tenv.borrow().module();
         self.tenv.borrow_mut().add_type(
             &module,
             &Alias::Variable(name.to_owned()),
             Type::Module(
-                w_assign.mod_name.to_owned(),
+                mod_name.clone(),
                 fn_ty.clone(),
+                count,
                 w_assign.span,
             ),
         )
@@ -377,6 +686,7 @@ impl Annotator {
             .map(|a| self.annotate_fn_app_arg(a))
             .collect();
 
+        self.check_init_args(&mod_name, &fn_args, &w_assign.span);
         self.tenv.borrow_mut().add_init(&module, &name, fn_args.clone());
 
         TyWeightsAssign {
@@ -384,11 +694,76 @@ impl Annotator {
             mod_name: w_assign.mod_name.clone(),
             fn_name: w_assign.fn_name.clone(),
             arg_ty: fn_args.to_ty(&w_assign.span),
+            dim_args,
             fn_args,
+            count,
             span: w_assign.span,
         }
     }
 
+    /// keyword arguments to `new` of a core op must be parameters of the op,
+    /// have the declared type and cover every parameter without a default
+    fn check_init_args(&self, mod_name: &str, args: &[TyFnAppArg], span: &ByteSpan) {
+        let defaults = match self.tenv.borrow().defaults(mod_name, "new") {
+            Some(defaults) => defaults,
+            None => return,
+        };
+        let sig = self.tenv.borrow().resolve_type(
+            &ModName::Named(mod_name.to_owned()),
+            &Alias::Function("new".to_owned()),
+        );
+        let params: Vec<(String, Type)> = match sig {
+            Some(Type::FUN(_, _, box Type::FnArgs(params, _), _, _)) => params
+                .into_iter()
+                .filter_map(|p| match p {
+                    Type::FnArg(Some(name), box ty, _) => Some((name, ty)),
+                    _ => None,
+                })
+                .collect(),
+            _ => return,
+        };
+
+        let mut em = self.emitter.borrow_mut();
+        for arg in args {
+            let name = arg.name.clone().unwrap();
+            match params.iter().find(|(p, _)| *p == name) {
+                None => em.add(Diag::UnknownInitArg(mod_name.to_owned(), name, arg.span)),
+                Some((_, ty)) => {
+                    let supplied = arg.arg.ty().with_span(&arg.span);
+                    if !accepts(ty, &supplied) {
+                        em.add(Diag::InitArgTypeMismatch(mod_name.to_owned(), name, ty.clone(), supplied));
+                    }
+                }
+            }
+        }
+        for (param, _) in &params {
+            let is_supplied = args.iter().any(|a| a.name.as_ref() == Some(param));
+            let is_optional = defaults.iter().any(|(p, _)| p == param);
+            if !is_supplied && !is_optional {
+                em.add(Diag::MissingInitArg(mod_name.to_owned(), param.to_owned(), *span));
+            }
+        }
+    }
+
+    /// `Mlp::<784, 128, 10>` supplies one dimension per parameter of `Mlp`
+    fn annotate_dim_args(&self, mod_name: &str, dims: &[Term], span: &ByteSpan) -> Vec<TyFnAppArg> {
+        let params = self.tenv.borrow().dim_params(&ModName::Named(mod_name.to_owned()));
+        if params.len() != dims.len() {
+            let e = Diag::DimArgsMismatch(mod_name.to_owned(), params.len(), dims.len(), *span);
+            self.emitter.borrow_mut().add(e);
+            return vec![];
+        }
+        params
+            .into_iter()
+            .zip(dims)
+            .map(|(name, dim)| TyFnAppArg {
+                name: Some(name),
+                arg: box self.annotate(dim),
+                span: *span,
+            })
+            .collect()
+    }
+
     fn annotate_fn_app_arg(&self, call: &FnAppArg) -> TyFnAppArg {
         let tyterm = Box::new(self.annotate(&call.arg));
         // println!("{}", tyterm);
@@ -414,6 +789,7 @@ impl Annotator {
             arg_ty,
             args: t_args,
             ret_ty: self.tenv.borrow_mut().fresh_var(*span),
+            index: None,
             span: *span,
         }
     }
@@ -461,19 +837,34 @@ impl Annotator {
                     Some(e) => e.name.clone(),
                     None => "x".to_string(),
                 };
-                if let Type::Module(ref _modn, Some(box Type::FUN(_,_,ref p, box ref r, _)), _) = mod_ty.clone() {
-                    let ty_sig = *p.clone();
-
-                    // type the first parameter
-                    decl.fn_params = vec![TyFnDeclParam {
-                        name: name0.clone(),
-                        ty: ty_sig.clone(),
-                        span: f.span,
-                    }];
-
-                    // override the old first argument which is []
-                    unsafe {
-                        self.tenv.borrow_mut().add_type_allow_replace(&module, &Alias::Variable(name0.clone()), ty_sig);
+                if let Type::Module(ref _modn, Some(box Type::FUN(_,_,ref p, box ref r, _)), ..) = mod_ty.clone() {
+                    // a single input is named `x` unless the parameter is declared,
+                    // multiple inputs take the names in the module signature
+                    let params = match **p {
+                        Type::FnArgs(ref args, _) => args
+                            .iter()
+                            .map(|a| match a {
+                                Type::FnArg(Some(ref name), box ref ty, _) => (name.clone(), ty.clone()),
+                                _ => unreachable!(),
+                            })
+                            .collect(),
+                        ref ty => vec![(name0, ty.clone())],
+                    };
+
+                    decl.fn_params = params
+                        .iter()
+                        .map(|(name, ty)| TyFnDeclParam {
+                            name: name.clone(),
+                            ty: ty.clone(),
+                            span: f.span,
+                        })
+                        .collect();
+
+                    // override the old arguments which are []
+                    for (name, ty) in params {
+                        unsafe {
+                            self.tenv.borrow_mut().add_type_allow_replace(&module, &Alias::Variable(name), ty);
+                        }
                     }
 
                     // // type the function return parameter
@@ -564,6 +955,7 @@ impl Annotator {
                         arg_ty: args_ty,
                         args,
                         ret_ty: self.tenv.borrow_mut().fresh_var(f_a.span),
+                        index: None,
                         span: f_a.span,
                     })
                 }
@@ -571,3 +963,25 @@ impl Annotator {
         }
     }
 }
+
+/// whether an argument of type `arg` can be passed for a parameter of type `param`
+fn accepts(param: &Type, arg: &Type) -> bool {
+    use self::Type::*;
+    match (param, arg) {
+        (INT(_), INT(_)) | (INT(_), ResolvedDim(..)) | (INT(_), DIM(..)) | (INT(_), DimExpr(..)) => true,
+        (FLOAT(_), FLOAT(_)) | (BOOL(_), BOOL(_)) => true,
+        (Tuple(ps, _), Tuple(xs, _)) => ps.len() == xs.len() && ps.iter().zip(xs).all(|(p, x)| accepts(p, x)),
+        // `kernel_size=5` is short for `kernel_size=(5, 5)`
+        (Tuple(ps, _), INT(_)) => ps.iter().all(|p| accepts(p, arg)),
+        (VAR(..), _) => true,
+        _ => false,
+    }
+}
+
+/// the type of a `let` pattern built from the types of its names in order
+fn pattern_ty<I: Iterator<Item = Type>>(pat: &Pattern, tys: &mut I, span: &ByteSpan) -> Type {
+    match pat {
+        Pattern::Ident(_) => tys.next().unwrap(),
+        Pattern::Tuple(items) => Type::Tuple(items.iter().map(|p| pattern_ty(p, tys, span)).collect(), *span),
+    }
+}
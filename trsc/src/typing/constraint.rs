@@ -9,13 +9,107 @@ use std::cell::RefCell;
 use errors::{ Emitter, Diag };
 
 use span::CSpan;
+use codespan::ByteSpan;
 
 #[derive(Debug, Hash, Eq, PartialEq, Clone, PartialOrd, Ord)]
 pub struct Equals(pub Type, pub Type);
 
+/// a constraint that can only be generated once the types of its inputs are
+/// known, such as the output shape of `Conv2d` or of a broadcasted binary op
+#[derive(Debug, Clone)]
+pub enum Deferred {
+    /// a method of a core op whose signature is resolved from its arguments
+    Op {
+        ty: Type,
+        mod_name: ModName,
+        fn_app: TyFnApp,
+        inits: Option<Vec<TyFnAppArg>>,
+    },
+    /// `ty = lhs op rhs`
+    BinOp(Type, Type, Type, ByteSpan),
+}
+
+impl Deferred {
+    /// the types that must be refined before it is worth trying again
+    pub fn inputs(&self) -> Vec<Type> {
+        match self {
+            Deferred::Op { fn_app, .. } => vec![fn_app.arg_ty.clone()],
+            Deferred::BinOp(_, lhs, rhs, _) => vec![lhs.clone(), rhs.clone()],
+        }
+    }
+
+    /// returns None if the inputs are not known well enough yet
+    pub fn resolve(&self, tenv: &Rc<RefCell<TypeEnv>>) -> Result<Option<Vec<Equals>>, Diag> {
+        match self {
+            Deferred::Op { ty, mod_name, fn_app, inits } => {
+                let resolution = tenv.borrow_mut().resolve_unresolved(
+                    ty,
+                    fn_app.name.as_str(),
+                    fn_app.arg_ty.clone(),
+                    fn_app.ret_ty.clone(),
+                    fn_app.args.clone(),
+                    inits.clone(),
+                )?;
+                let (resolved_fn_ty, is_stateful) = match resolution {
+                    Some(r) => r,
+                    None => return Ok(None),
+                };
+                // whatever the op built without a location comes from this call
+                let resolved_fn_ty = resolved_fn_ty.fill_span(&fn_app.span);
+                let symbol_name = fn_app.mod_name.clone().unwrap();
+                // set alias for symbol if stateful, only forward calls are replaced
+                let is_forward = fn_app.name.as_str() == "forward";
+                if let (true, true, Some(orig_name)) = (is_stateful, is_forward, &fn_app.orig_name) {
+                    tenv.borrow_mut().infer_inits(ty, mod_name, orig_name, &resolved_fn_ty);
+                    let ty = match resolved_fn_ty.clone() {
+                        Type::FUN(m,n,a,r,s) => Type::FUN(m,n, box a.first_arg_ty().unwrap(),r,s),
+                        _ => unimplemented!(),
+                    };
+                    let sp = ty.span();
+                    let alias = Alias::Variable(orig_name.to_owned());
+                    // a list of modules stays a list
+                    let count = match tenv.borrow().resolve_type(mod_name, &alias) {
+                        Some(Type::Module(_, _, count, _)) => count,
+                        _ => None,
+                    };
+                    unsafe {
+                        tenv.borrow_mut().replace_type(
+                            mod_name,
+                            &alias,
+                            Type::Module(symbol_name.to_owned(), Some(box ty), count, sp),
+                        );
+                    }
+                }
+                Ok(Some(vec![Equals(
+                    resolved_fn_ty,
+                    fun!(symbol_name, fn_app.name.as_str(), fn_app.arg_ty.clone(), fn_app.ret_ty.clone()),
+                )]))
+            }
+            Deferred::BinOp(ty, lhs, rhs, sp) => bin_op_eqs(ty, lhs, rhs, sp),
+        }
+    }
+
+    /// what could not be inferred and from which input
+    pub fn describe(&self) -> (String, Type, ByteSpan) {
+        match self {
+            Deferred::Op { fn_app, .. } => (
+                fn_app.orig_name.clone().or_else(|| fn_app.mod_name.clone()).unwrap(),
+                fn_app.arg_ty.first_arg_ty().unwrap_or_else(|| fn_app.arg_ty.clone()),
+                fn_app.span,
+            ),
+            Deferred::BinOp(_, lhs, rhs, sp) => (
+                "binary operation".to_owned(),
+                Type::Tuple(vec![lhs.clone(), rhs.clone()], *sp),
+                *sp,
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Constraints {
     pub set: BTreeSet<Equals>,
+    pub deferred: Vec<Deferred>,
     pub emitter: Rc<RefCell<Emitter>>,
     pub tenv: Rc<RefCell<TypeEnv>>,
 }
@@ -25,20 +119,26 @@ impl Constraints {
     pub fn new(emitter: Rc<RefCell<Emitter>>, tenv: Rc<RefCell<TypeEnv>>) -> Self {
         Constraints {
             set: BTreeSet::new(),
+            deferred: vec![],
             emitter,
             tenv,
         }
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.set.is_empty()
-    }
-
     fn add(&mut self, a: Type, b: Type) {
         // println!("{:?} {:?}", a, b);
         self.set.insert(Equals(a, b));
     }
 
+    /// resolve now if possible, otherwise leave it to the unifier
+    fn defer(&mut self, d: Deferred) {
+        match d.resolve(&self.tenv) {
+            Ok(Some(eqs)) => self.set.extend(eqs),
+            Ok(None) => self.deferred.push(d),
+            Err(e) => self.emitter.borrow_mut().add(e),
+        }
+    }
+
     pub fn collect(&mut self, typed_term: &TyTerm) {
         use self::TyTerm::*;
         let module = { self.tenv.borrow().module().clone() };
@@ -50,6 +150,7 @@ impl Constraints {
                 .collect(),
             TyInteger(_, _, _) => (),
             TyFloat(_, _, _) => (),
+            TyBool(_, _, _) => (),
             TyList(ref terms) => terms.iter().map(|t| self.collect(&t)).collect(),
             TyTuple(_, ref terms, _) => terms.iter().map(|t| self.collect(&t)).collect(),
             TyIdent(ref t, ref name, ref sp) => {
@@ -61,25 +162,75 @@ impl Constraints {
                     .with_span(&sp);
                 self.add(t.clone(), ty);
             }
-            // &TyFieldAccess(TyFieldAccess),
+            TyFieldAccess(ref f_a) => self.collect_field_access(f_a),
             TyFnApp(ref fn_app) => self.collect_fn_app(&fn_app),
             TyBlock { ref stmts, ref ret, .. } => {
+                let depth = self.tenv.borrow().scope_depth(&module);
                 self.tenv.borrow_mut().push_scope_collection(&module);
                 self.collect(&stmts);
                 self.collect(&ret);
-                self.tenv.borrow_mut().pop_scope(&module);
+                self.tenv.borrow_mut().pop_scopes_to_collection(&module, depth);
             }
             TyExpr(ref items, ref ty, _) => {
                 self.collect(&items);
                 self.add(ty.clone(), items.ty());
             }
             TyStmt(ref items, _) => self.collect(&items),
-            TyNone => (),
-            _ => {
-                panic!("{:#?}", typed_term);
+            TyBinOp(ref ty, _, ref lhs, ref rhs, ref sp) => {
+                self.collect(&lhs);
+                self.collect(&rhs);
+                self.collect_bin_op(ty, &lhs.ty(), &rhs.ty(), sp);
+            }
+            TyLet(ref ty, _, ref rhs, _) | TyLetTuple(ref ty, _, ref rhs, _) => {
+                self.collect(&rhs);
+                self.add(ty.clone(), rhs.ty());
+                self.tenv.borrow_mut().push_scope_collection(&module);
             }
+            TyAssign(ref ty, _, ref rhs, _) => {
+                self.collect(&rhs);
+                self.add(ty.clone(), rhs.ty());
+            }
+            TyForLoop { ref body, .. } => {
+                self.tenv.borrow_mut().push_scope_collection(&module);
+                self.collect(&body);
+                self.tenv.borrow_mut().pop_scope_collection(&module);
+            }
+            TyConditional {
+                ref ty,
+                ref cond,
+                ref then,
+                ref els,
+                ref span,
+            } => {
+                self.collect(&cond);
+                self.collect(&then);
+                self.collect(&els);
+                self.add(cond.ty(), Type::BOOL(cond.span()));
+                // without an else branch, `if` is a statement
+                if let TyNone = **els {
+                    self.add(ty.clone(), Type::Unit(*span));
+                } else {
+                    self.add(ty.clone(), then.ty());
+                    self.add(ty.clone(), els.ty());
+                }
+            }
+            TyNone => (),
+        }
+    }
+    /// `self.training` is the train/eval flag of the module
+    fn collect_field_access(&mut self, f_a: &TyFieldAccess) {
+        if f_a.mod_name == "self" && f_a.field_name == "training" {
+            self.add(f_a.ty.clone(), Type::BOOL(f_a.span));
         }
     }
+
+    /// the result of an elementwise binary op is the broadcasted shape of
+    /// its operands. If an operand is not yet inferred, the constraint is
+    /// deferred until its type is substituted.
+    fn collect_bin_op(&mut self, ty: &Type, lhs: &Type, rhs: &Type, sp: &ByteSpan) {
+        self.defer(Deferred::BinOp(ty.clone(), lhs.clone(), rhs.clone(), *sp));
+    }
+
     fn collect_decl(&mut self, decl: &TyDecl) {
         use self::TyDecl::*;
         match decl {
@@ -104,6 +255,7 @@ impl Constraints {
             Type::Module(
                 decl.name.to_owned(),
                 Some(box decl.ty_sig.clone()),
+                None,
                 decl.span,
             ),
             graph_ty_sig,
@@ -127,7 +279,7 @@ impl Constraints {
         //     panic!("{:?}, {:?}", decl.fn_ty, func);
         // }
 
-        self.tenv.borrow_mut().pop_scope(&module);
+        self.tenv.borrow_mut().pop_scope_collection(&module);
     }
 
     fn collect_node_decl(&mut self, decl: &TyNodeDecl) {
@@ -140,6 +292,7 @@ impl Constraints {
             Type::Module(
                 decl.name.to_owned(),
                 Some(box decl.ty_sig.clone()),
+                None,
                 decl.span,
             ),
             graph_ty_sig,
@@ -156,6 +309,7 @@ impl Constraints {
             Type::Module(
                 decl.name.to_owned(),
                 Some(box decl.ty_sig.clone()),
+                None,
                 decl.span,
             ),
             graph_ty_sig,
@@ -183,6 +337,7 @@ impl Constraints {
                 arg_ty: w_a.arg_ty.clone(),
                 ret_ty,
                 args: w_a.fn_args.clone(),
+                index: None,
                 span: w_a.span,
             },
         );
@@ -194,6 +349,11 @@ impl Constraints {
         // println!("{}", fn_app.name);
         // println!("{:#?}", cs);
 
+        if let Some(ref idx) = fn_app.index {
+            self.collect(&idx);
+            self.add(idx.ty(), Type::INT(idx.span()));
+        }
+
         let symbol_name = fn_app.mod_name.clone().unwrap();
         let symbol_mod_ty = match &fn_app.orig_name {
             Some(ref orig_name) => self.tenv.borrow()
@@ -228,12 +388,24 @@ impl Constraints {
         };
 
         let symbol_modname = ModName::Named(symbol_mod_ty.as_string()); // Linear
+        // each instance of a generic node has its own signature,
+        // the methods of the node itself stay polymorphic
+        if self.tenv.borrow().is_generic(&symbol_modname) {
+            self.collect_node_app(fn_app, &symbol_mod_ty);
+            return;
+        }
         let fn_name = &fn_app.name; // F(forward)
         let resolved_ty = self.tenv.borrow().resolve_type(&symbol_modname, &fn_name) // function / Unresolved
                     .or_else(|| self.tenv.borrow().resolve_type(&ModName::Global, &fn_name));
-        let ty = match resolved_ty {
-            Some(ty) => ty,
-            None => {
+        let ty = match (resolved_ty, &symbol_mod_ty) {
+            (Some(ty), _) => ty,
+            // user-defined node whose graph is declared further down:
+            // its declared signature stands in for the methods
+            (None, Type::Module(_, Some(_), ..)) => {
+                self.collect_node_app(fn_app, &symbol_mod_ty);
+                return;
+            }
+            (None, _) => {
                 let e = Diag::SymbolNotFound(fn_name.as_str().to_owned(), fn_app.span);
                 self.emitter.borrow_mut().add(e); // ...
                 return;
@@ -246,82 +418,39 @@ impl Constraints {
         // );
 
         if let Type::UnresolvedModuleFun(..) = ty {
-            let resolution = if fn_app.orig_name.is_none() { // this is a weight assign fn
-                // println!("{:?}, {:?}", &fn_app.mod_name.clone().unwrap().as_str(), fn_app.name);
-                self.tenv.borrow_mut().resolve_unresolved(
-                    &ty,
-                    &fn_app.name.as_str(),
-                    fn_app.arg_ty.clone(),
-                    fn_app.ret_ty.clone(),
-                    fn_app.args.clone(),
-                    None
-                )
-            } else {
-                let inits = self.tenv.borrow().resolve_init(&current_mod, &fn_app.orig_name.clone().unwrap());
-                self.tenv.borrow_mut().resolve_unresolved(
-                    &ty,
-                    fn_name.as_str(),
-                    fn_app.arg_ty.clone(),
-                    fn_app.ret_ty.clone(),
-                    fn_app.args.clone(),
-                    inits
-                )
+            // weight assigns have no initialization of their own
+            let inits = match fn_app.orig_name {
+                Some(ref orig_name) => self.tenv.borrow().resolve_init(&current_mod, orig_name),
+                None => None,
             };
-
-            match resolution {
-                Ok(Some((resolved_fn_ty, is_stateful))) => {
-                    self.add(
-                        resolved_fn_ty.clone(),
-                        fun!(
-                            symbol_name,
-                            fn_app.name.as_str(),
-                            fn_app.arg_ty.clone(),
-                            fn_app.ret_ty.clone()
-                        )
-                    );
-                    // set alias for symbol if stateful
-                    if is_stateful {
-                        unsafe {
-                            // println!("{:#?}", self.tenv);
-                            let ty = match resolved_fn_ty {
-                                Type::FUN(m,n,a,r,s) => Type::FUN(m,n, box a.first_arg_ty().unwrap(),r,s),
-                                _ => unimplemented!(),
-                            };
-                            let sp = ty.span();
-
-                            if fn_app.orig_name.is_some() {
-                                // only replace forward calls
-                                self.tenv.borrow_mut().replace_type(
-                                    &current_mod,
-                                    &Alias::Variable(fn_app.orig_name.clone().unwrap().to_owned()),
-                                    Type::Module(symbol_name.to_owned(), Some(box ty), sp),
-                                );
-                            }
-                            // println!("{:#?}", self.tenv);
-                            // panic!();
-                        }
-                    }
-                }
-                Ok(None) =>
-                    (),
-                Err(e) => {
-                    self.emitter.borrow_mut().add(e);
-                }
-            }
+            self.defer(Deferred::Op {
+                ty: ty.clone(),
+                mod_name: current_mod.clone(),
+                fn_app: fn_app.shallow(),
+                inits,
+            });
+        } else {
+            self.add(
+                ty.clone(),
+                fun!(symbol_name, fn_app.name.as_str(), fn_app.arg_ty.clone(), fn_app.ret_ty.clone()),
+            );
         }
 
-        self.add(
-            ty.clone(),
-            fun!(symbol_name, fn_app.name.as_str(), fn_app.arg_ty.clone(), fn_app.ret_ty.clone()),
-        );
+        self.collect_node_app(fn_app, &symbol_mod_ty);
+    }
 
+    /// constrains a call against the signature carried by the module type
+    fn collect_node_app(&mut self, fn_app: &TyFnApp, symbol_mod_ty: &Type) {
         self.add(fn_app.arg_ty.clone(), fn_app.args.to_ty(&fn_app.span));
 
-        if let "forward" = fn_name.as_str() {
-            if let Type::Module(_, Some(box supplied_ty), _) = symbol_mod_ty {
-                if let Type::FUN(_,_,box p,box r, _) = supplied_ty {
-                    self.add(fn_app.arg_ty.clone().clone(),
-                        args!(arg!("x",p.clone())));
+        if let "forward" = fn_app.name.as_str() {
+            if let Type::Module(_, Some(box supplied_ty), ..) = symbol_mod_ty {
+                if let Type::FUN(_,_,box p,box r, _) = supplied_ty.clone() {
+                    let p = match p {
+                        Type::FnArgs(..) => p,
+                        _ => args!(arg!("x", p)),
+                    };
+                    self.add(fn_app.arg_ty.clone(), p);
                     self.add(fn_app.ret_ty.clone(), r.clone());
                 }
             }
@@ -330,7 +459,65 @@ impl Constraints {
         for a in &fn_app.args {
             self.collect(&a.arg);
         }
-
     }
 }
 
+/// equations for `ty = lhs op rhs`, None if an operand is not yet inferred
+fn bin_op_eqs(ty: &Type, lhs: &Type, rhs: &Type, sp: &ByteSpan) -> Result<Option<Vec<Equals>>, Diag> {
+    use self::Type::*;
+    let mut eqs = vec![];
+    let ret = match (lhs, rhs) {
+        (VAR(..), _) | (_, VAR(..)) | (DIM(..), _) | (_, DIM(..)) => return Ok(None),
+        (TSR(..), TSR(..)) => {
+            let (ret, dims_eqs) = broadcast(lhs, rhs)?;
+            eqs = dims_eqs;
+            // no implicit promotion between element types
+            eqs.push(Equals(lhs.dtype().unwrap(), rhs.dtype().unwrap()));
+            ret
+        }
+        (TSR(..), _) if rhs.is_scalar() => lhs.clone(),
+        (_, TSR(..)) if lhs.is_scalar() => rhs.clone(),
+        (FLOAT(_), _) | (_, FLOAT(_)) if lhs.is_scalar() && rhs.is_scalar() => float!(),
+        _ if lhs.is_scalar() && rhs.is_scalar() => int!(),
+        _ => return Ok(Some(eqs)),
+    };
+    eqs.push(Equals(ty.clone(), ret.with_span(sp)));
+    Ok(Some(eqs))
+}
+
+/// numpy-style broadcasting: dimensions are aligned from the right and
+/// a dimension of size 1 stretches to match the other operand
+fn broadcast(ts1: &Type, ts2: &Type) -> Result<(Type, Vec<Equals>), Diag> {
+    let dims1 = ts1.as_vec().unwrap();
+    let dims2 = ts2.as_vec().unwrap();
+    let rank = dims1.len().max(dims2.len());
+    let mut dims = vec![];
+    let mut eqs = vec![];
+    for i in 1..=rank {
+        let d1 = if i <= dims1.len() { Some(&dims1[dims1.len() - i]) } else { None };
+        let d2 = if i <= dims2.len() { Some(&dims2[dims2.len() - i]) } else { None };
+        let dim = match (d1, d2) {
+            (Some(a), None) | (None, Some(a)) => a.clone(),
+            (Some(a), Some(b)) => match (a.as_num(), b.as_num()) {
+                (Some(1), _) => b.clone(),
+                (_, Some(1)) => a.clone(),
+                (Some(m), Some(n)) => {
+                    if m != n {
+                        return Err(Diag::DimensionMismatch(a.clone(), b.clone()));
+                    }
+                    a.clone()
+                }
+                _ => {
+                    if a != b {
+                        eqs.push(Equals(a.clone(), b.clone()));
+                    }
+                    a.clone()
+                }
+            },
+            (None, None) => unreachable!(),
+        };
+        dims.push(dim);
+    }
+    dims.reverse();
+    Ok((Type::TSR(dims, box ts1.dtype().unwrap(), ts1.span()), eqs))
+}
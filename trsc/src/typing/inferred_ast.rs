@@ -10,9 +10,13 @@ pub fn subs(typed_term: &TyTerm, s: &mut Substitution) -> TyTerm {
         TyProgram(ref decls) => TyProgram(decls.iter().map(|decl| subs_decl(&decl, s)).collect()),
         TyInteger(ref ty, ref a, ref sp) => TyInteger(s.apply_ty(&ty), *a, *sp),
         TyFloat(ref ty, ref a, ref sp) => TyFloat(s.apply_ty(&ty), *a, *sp),
+        TyBool(ref ty, ref a, ref sp) => TyBool(s.apply_ty(&ty), *a, *sp),
         TyList(ref terms) => TyList(terms.iter().map(|t| subs(&t, s)).collect()),
         TyIdent(ref t, ref name, ref span) => TyIdent(s.apply_ty(t), name.clone(), *span),
-        // // &TyFieldAccess(TyFieldAccess),
+        TyFieldAccess(ref f_a) => TyFieldAccess(typed_term::TyFieldAccess {
+            ty: s.apply_ty(&f_a.ty),
+            ..f_a.clone()
+        }),
         TyFnApp(ref fn_app) => TyFnApp(box subs_fn_app(&fn_app, s)),
         TyBlock {
             ref stmts,
@@ -38,9 +42,57 @@ pub fn subs(typed_term: &TyTerm, s: &mut Substitution) -> TyTerm {
             vs.iter().map(|i|subs(i,s)).collect(),
             *span
         ),
-        _ => {
-            panic!("{:#?}", typed_term);
-        }
+        TyBinOp(ref ty, ref op, ref lhs, ref rhs, ref span) => TyBinOp(
+            s.apply_ty(ty),
+            *op,
+            box subs(&lhs, s),
+            box subs(&rhs, s),
+            *span,
+        ),
+        TyLet(ref ty, ref name, ref rhs, ref span) => TyLet(
+            s.apply_ty(ty),
+            name.clone(),
+            box subs(&rhs, s),
+            *span,
+        ),
+        TyLetTuple(ref ty, ref names, ref rhs, ref span) => TyLetTuple(
+            s.apply_ty(ty),
+            names.clone(),
+            box subs(&rhs, s),
+            *span,
+        ),
+        TyAssign(ref ty, ref name, ref rhs, ref span) => TyAssign(
+            s.apply_ty(ty),
+            name.clone(),
+            box subs(&rhs, s),
+            *span,
+        ),
+        TyForLoop {
+            ref var,
+            ref from,
+            ref to,
+            ref body,
+            ref span,
+        } => TyForLoop {
+            var: var.clone(),
+            from: *from,
+            to: *to,
+            body: box subs(&body, s),
+            span: *span,
+        },
+        TyConditional {
+            ref ty,
+            ref cond,
+            ref then,
+            ref els,
+            ref span,
+        } => TyConditional {
+            ty: s.apply_ty(ty),
+            cond: box subs(&cond, s),
+            then: box subs(&then, s),
+            els: box subs(&els, s),
+            span: *span,
+        },
     }
 }
 
@@ -97,16 +149,22 @@ fn subs_use_stmt(decl: &TyUseStmt, _tenv: &mut Substitution) -> TyUseStmt {
 fn subs_weights_assign(w_a: &TyWeightsAssign, s: &mut Substitution) -> TyWeightsAssign {
     let mut c = w_a.clone();
     c.arg_ty = s.apply_ty(&c.arg_ty);
+    c.dim_args = c.dim_args.iter().map(|a| subs_fn_app_arg(a, s)).collect();
     c.fn_args = c.fn_args.iter().map(|a| subs_fn_app_arg(a, s)).collect();
     c
 }
 
 fn subs_fn_app(fn_app: &typed_term::TyFnApp, s: &mut Substitution) -> typed_term::TyFnApp {
-    let mut c = fn_app.clone();
-    c.arg_ty = s.apply_ty(&c.arg_ty);
-    c.ret_ty = s.apply_ty(&c.ret_ty);
-    c.args = c.args.iter().map(|a| subs_fn_app_arg(&a, s)).collect();
-    c
+    typed_term::TyFnApp {
+        mod_name: fn_app.mod_name.clone(),
+        orig_name: fn_app.orig_name.clone(),
+        name: fn_app.name.clone(),
+        arg_ty: s.apply_ty(&fn_app.arg_ty),
+        ret_ty: s.apply_ty(&fn_app.ret_ty),
+        args: fn_app.args.iter().map(|a| subs_fn_app_arg(&a, s)).collect(),
+        index: fn_app.index.as_ref().map(|i| box subs(&i, s)),
+        span: fn_app.span,
+    }
 }
 
 fn subs_fn_app_arg(a: &TyFnAppArg, s: &mut Substitution) -> TyFnAppArg {
@@ -10,7 +10,7 @@ use std::cell::RefCell;
 /// 1. Type Aliasing during the first pass (annotate)
 /// 2. pushing and popping scopes (during `annotate` and `collect`)
 /// 3. module type and method type reconstruction
-use parsing::term::{AliasAssign, TensorTy, Term};
+use parsing::term::{AliasAssign, DType, TensorTy, Term};
 use std::collections::{BTreeMap, VecDeque};
 use std::fmt::{Debug, Error, Formatter};
 use typing::typed_term::TyFnAppArg;
@@ -45,16 +45,19 @@ impl Debug for ModName {
 }
 
 /// Represents a single level of scope
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Scope {
     /// type information of aliases
     types: BTreeMap<Alias, Type>,
+    /// position in the scope queue, in the order the scopes are pushed
+    slot: usize,
 }
 
 impl Scope {
     pub fn new() -> Scope {
         Scope {
             types: BTreeMap::new(),
+            slot: 0,
         }
     }
 }
@@ -62,6 +65,8 @@ impl Scope {
 type ScopeStack = VecDeque<Scope>;
 type ScopeQueue = VecDeque<Scope>;
 type InitMap = BTreeMap<String, Vec<TyFnAppArg>>;
+/// index of the next scope in the queue during constraint collection
+type ScopeCursor = usize;
 
 #[derive(Debug)]
 pub struct TypeEnv {
@@ -69,7 +74,9 @@ pub struct TypeEnv {
     dim_counter: TypeId,
     var_counter: TypeId,
     current_mod: ModName,
-    modules: BTreeMap<ModName, (ScopeStack, ScopeQueue, InitMap)>,
+    modules: BTreeMap<ModName, (ScopeStack, ScopeQueue, InitMap, ScopeCursor)>,
+    /// dimension parameters of generic nodes
+    dim_params: BTreeMap<ModName, Vec<String>>,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
@@ -104,6 +111,7 @@ impl TypeEnv {
             var_counter: 0,
             current_mod: Global,
             modules: BTreeMap::new(),
+            dim_params: BTreeMap::new(),
         };
 
         // import basic functions such as view
@@ -118,6 +126,18 @@ impl TypeEnv {
         Type::DIM(self.dim_counter, span)
     }
 
+    /// create new variadic dimensions `...`
+    pub fn fresh_dims(&mut self, span: ByteSpan) -> Type {
+        self.dim_counter += 1;
+        Type::DIMS(self.dim_counter, span)
+    }
+
+    /// a tensor of any rank and element type
+    pub fn fresh_tsr(&mut self, span: ByteSpan) -> Type {
+        let dims = self.fresh_dims(span);
+        Type::TSR(vec![dims], box self.fresh_var(span), span)
+    }
+
     /// create new type variable
     pub fn fresh_var(&mut self, span: ByteSpan) -> Type {
         self.var_counter += 1;
@@ -127,13 +147,19 @@ impl TypeEnv {
     /// push scope onto stack during tree traversal
     pub fn push_scope(&mut self, mod_name: &ModName) {
         let stack = self.modules.get_mut(mod_name).unwrap();
-        stack.0.push_back(Scope::new());
+        // reserve a slot so the queue is in the same order as the traversal
+        let mut scp = Scope::new();
+        scp.slot = stack.1.len();
+        stack.1.push_back(Scope::new());
+        stack.0.push_back(scp);
     }
 
     /// during constraint collection, push the popped scopes back
+    /// in the order they were pushed in the first traversal
     pub fn push_scope_collection(&mut self, mod_name: &ModName) {
         let stack = self.modules.get_mut(mod_name).unwrap();
-        let scp = stack.1.pop_front().unwrap();
+        let scp = stack.1[stack.3].clone();
+        stack.3 = (stack.3 + 1) % stack.1.len();
         stack.0.push_back(scp);
     }
 
@@ -141,7 +167,34 @@ impl TypeEnv {
     pub fn pop_scope(&mut self, mod_name: &ModName) {
         let stack = self.modules.get_mut(mod_name).unwrap();
         let popped = stack.0.pop_back().unwrap();
-        stack.1.push_back(popped);
+        let slot = popped.slot;
+        stack.1[slot] = popped;
+    }
+
+    /// exiting block during constraint collection
+    pub fn pop_scope_collection(&mut self, mod_name: &ModName) {
+        let stack = self.modules.get_mut(mod_name).unwrap();
+        stack.0.pop_back().unwrap();
+    }
+
+    /// number of block scopes currently on the stack
+    pub fn scope_depth(&self, mod_name: &ModName) -> usize {
+        self.modules[mod_name].0.len()
+    }
+
+    /// pop scopes until `depth` is reached, used to exit a block
+    /// along with the scopes pushed by its `let` bindings
+    pub fn pop_scopes_to(&mut self, mod_name: &ModName, depth: usize) {
+        while self.scope_depth(mod_name) > depth {
+            self.pop_scope(mod_name);
+        }
+    }
+
+    /// same as `pop_scopes_to` during constraint collection
+    pub fn pop_scopes_to_collection(&mut self, mod_name: &ModName, depth: usize) {
+        while self.scope_depth(mod_name) > depth {
+            self.pop_scope_collection(mod_name);
+        }
     }
 
     pub fn resolve_init(&self, mod_name: &ModName, alias: &str) -> Option<Vec<TyFnAppArg>> {
@@ -157,10 +210,10 @@ impl TypeEnv {
     }
 
     /// inside the module or global scope, iterate over block scope and find
-    /// the last defn of the alias which may be shadowed
+    /// the innermost defn of the alias which may shadow the outer ones
     fn resolve_type_inner(&self, mod_name: &ModName, alias: &Alias) -> Option<Type> {
         let types = self.get_scoped_types(mod_name, alias);
-        types.iter().last().cloned()
+        types.first().cloned()
     }
 
     /// iterate over scopes and find the alias in each
@@ -185,7 +238,7 @@ impl TypeEnv {
                 // if the module does not yet exist, add with an empty scope
                 let mut q = VecDeque::new();
                 q.push_back(Scope::new());
-                (q, VecDeque::new(), BTreeMap::new())
+                (q, VecDeque::new(), BTreeMap::new(), 0)
             });
         }
     }
@@ -196,7 +249,7 @@ impl TypeEnv {
             // if the module does not yet exist, add with an empty scope
             let mut q = VecDeque::new();
             q.push_back(Scope::new());
-            (q, VecDeque::new(), BTreeMap::new())
+            (q, VecDeque::new(), BTreeMap::new(), 0)
         });
 
         let top = stack.0.len() - 1;
@@ -223,7 +276,7 @@ impl TypeEnv {
             // if the module does not yet exist, add with an empty scope
             let mut q = VecDeque::new();
             q.push_back(Scope::new());
-            (q, VecDeque::new(), BTreeMap::new())
+            (q, VecDeque::new(), BTreeMap::new(), 0)
         });
 
         let top = stack.0.len() - 1;
@@ -240,7 +293,7 @@ impl TypeEnv {
             // if the module does not yet exist, add with an empty scope
             let mut q = VecDeque::new();
             q.push_back(Scope::new());
-            (q, VecDeque::new(), BTreeMap::new())
+            (q, VecDeque::new(), BTreeMap::new(), 0)
         });
 
         for scope in &mut stack.0 {
@@ -260,21 +313,108 @@ impl TypeEnv {
         let _ = stack.2.insert(alias.to_owned(), ty);
     }
 
+    /// complete the initialization of a stateful op with the arguments
+    /// its resolved signature `fn_ty` determines, so that codegen sees them
+    pub fn infer_inits(&mut self, ty: &Type, mod_name: &ModName, alias: &str, fn_ty: &Type) {
+        let (path_name, op_name) = match ty {
+            Type::UnresolvedModuleFun(ref p0, ref p1, ..) => (p0, p1),
+            _ => return,
+        };
+        let inits = match self.resolve_init(mod_name, alias) {
+            Some(inits) => inits,
+            None => return,
+        };
+        let inferred = match self.core.borrow().find(path_name, op_name) {
+            Some(op) => op.infer_inits(fn_ty, &inits),
+            None => return,
+        };
+        if inferred.is_empty() {
+            return;
+        }
+        let stack = self.modules.get_mut(mod_name).unwrap();
+        stack.2.get_mut(alias).unwrap().extend(inferred);
+    }
+
     /// tie an alias with a type variable dimension
     pub fn add_dim_alias(&mut self, mod_name: &ModName, alias: &Alias, span: ByteSpan) -> Result<(), Diag> {
-        let tyvar = self.fresh_dim(span);
+        let tyvar = self.fresh_dim_for(alias.as_str(), span);
         self.add_type(mod_name, alias, tyvar)
     }
 
-    /// tie an alias with a resolved dimension
+    /// declare the dimension parameters of a generic node,
+    /// each one is a dimension variable inside the node
+    pub fn add_dim_params(&mut self, mod_name: &ModName, params: &[String], span: ByteSpan) -> Result<(), Diag> {
+        for param in params {
+            self.add_dim_alias(mod_name, &Alias::Variable(param.to_owned()), span)?;
+        }
+        self.dim_params.insert(mod_name.clone(), params.to_vec());
+        Ok(())
+    }
+
+    pub fn dim_params(&self, mod_name: &ModName) -> Vec<String> {
+        self.dim_params.get(mod_name).cloned().unwrap_or_default()
+    }
+
+    pub fn is_generic(&self, mod_name: &ModName) -> bool {
+        !self.dim_params(mod_name).is_empty()
+    }
+
+    /// copy the signature of a generic node for one instantiation:
+    /// dimension parameters take the supplied values and all other
+    /// dimension variables are made fresh
+    pub fn instantiate(&mut self, mod_name: &ModName, ty: &Type, dims: Vec<Type>) -> Type {
+        let mut subs = BTreeMap::new();
+        for (param, dim) in self.dim_params(mod_name).iter().zip(dims) {
+            if let Some(Type::DIM(id, _)) = self.resolve_type(mod_name, &Alias::Variable(param.to_owned())) {
+                subs.insert(id, dim);
+            }
+        }
+        self.fresh_copy(ty, &mut subs)
+    }
+
+    fn fresh_copy(&mut self, ty: &Type, subs: &mut BTreeMap<TypeId, Type>) -> Type {
+        use self::Type::*;
+        match ty {
+            DIM(id, sp) | DIMS(id, sp) => {
+                if let Some(dim) = subs.get(id) {
+                    return dim.with_span(sp);
+                }
+                let dim = match ty {
+                    DIMS(..) => self.fresh_dims(*sp),
+                    _ => self.fresh_dim(*sp),
+                };
+                subs.insert(*id, dim.clone());
+                dim
+            }
+            DimExpr(op, l, r, sp) => {
+                let l = self.fresh_copy(l, subs);
+                let r = self.fresh_copy(r, subs);
+                Type::dim_op(*op, l, r, *sp)
+            }
+            TSR(dims, dt, sp) => TSR(dims.iter().map(|d| self.fresh_copy(d, subs)).collect(), dt.clone(), *sp),
+            Tuple(tys, sp) => Tuple(tys.iter().map(|t| self.fresh_copy(t, subs)).collect(), *sp),
+            FnArgs(args, sp) => FnArgs(args.iter().map(|a| self.fresh_copy(a, subs)).collect(), *sp),
+            FnArg(name, a, sp) => FnArg(name.clone(), box self.fresh_copy(a, subs), *sp),
+            FUN(m, n, p, r, sp) => {
+                let p = self.fresh_copy(p, subs);
+                let r = self.fresh_copy(r, subs);
+                FUN(m.clone(), n.clone(), box p, box r, *sp)
+            }
+            _ => ty.clone(),
+        }
+    }
+
+    /// tie an alias with a dimension expression such as `C * H * W`,
+    /// which is evaluated if all of its operands are resolved
     pub fn add_resolved_dim_alias(
         &mut self,
         mod_name: &ModName,
         alias: &Alias,
-        num: i64,
+        rhs: &Term,
         span: &ByteSpan,
     ) -> Result<(), Diag> {
-        let tyvar = Type::ResolvedDim(num, *span);
+        let tyvar = self.eval_dim(mod_name, rhs, span).with_span(span);
+        check_folded(&tyvar)?;
         self.add_type(mod_name, alias, tyvar)
     }
 
@@ -283,59 +423,96 @@ impl TypeEnv {
         &mut self,
         mod_name: &ModName,
         alias: &Alias,
-        tsr: &[String],
+        tsr: &[Term],
+        dtype: Option<DType>,
         span: &ByteSpan,
     ) -> Result<(), Diag> {
         // first insert all the dims
-        for t in tsr.iter() {
+        for t in tsr.iter().flat_map(dim_aliases) {
             let alias = Alias::Variable(t.to_string());
-            if !self.exists(mod_name, &alias) {
+            if !self.exists(mod_name, &alias) && !self.exists(&Global, &alias) {
                 self.add_dim_alias(mod_name, &alias, *span)?;
             }
         }
 
         // then insert the tensor itself
-        let tsr = self.create_tensor(mod_name, tsr, span);
+        let dtype = self.resolve_dtype(dtype, span);
+        let tsr = self.create_tensor(mod_name, tsr, dtype, span);
         self.add_type(mod_name, alias, tsr)
     }
 
+    /// tensors in a type signature without a declared dtype hold `f32`
+    pub fn resolve_dtype(&self, dtype: Option<DType>, span: &ByteSpan) -> Type {
+        Type::DTYPE(dtype.unwrap_or(DType::F32), *span)
+    }
+
     // make a new tensor based on type signature
     pub fn create_tensor(
         &mut self,
         mod_name: &ModName,
-        dims: &[String],
+        dims: &[Term],
+        dtype: Type,
         span: &ByteSpan,
     ) -> Type {
         // each dimension alias in the tensor type signature must exist
         let dims_ty = dims.iter()
             .map(|t| {
-                match t.parse::<i64>() {
-                    Ok(i) => vec![Type::ResolvedDim(i, *span)],
-                    Err(_e) => {
-                        let alias = Alias::Variable(t.to_string());
-                        let ty = self.resolve_type(mod_name, &alias)
-                            .or_else(|| self.resolve_type(&Global, &alias))
-                            .unwrap_or_else(|| self.fresh_dim(*span))
-                            .clone();
-                        if let Type::TSR(vs, _) = ty {
+                match t {
+                    Term::Ident(ref id, _) => {
+                        let ty = self.resolve_dim_alias(mod_name, id, span);
+                        if let Type::TSR(vs, _, _) = ty {
                             vs
                         } else {
                             vec![ty]
                         }
                     }
+                    _ => vec![self.eval_dim(mod_name, t, span)],
                 }
             })
             .flatten()
             .collect();
         // create the tensor type
-        Type::TSR(dims_ty, *span)
+        Type::TSR(dims_ty, box dtype, *span)
+    }
+
+    fn resolve_dim_alias(&mut self, mod_name: &ModName, id: &str, span: &ByteSpan) -> Type {
+        let alias = Alias::Variable(id.to_string());
+        self.resolve_type(mod_name, &alias)
+            .or_else(|| self.resolve_type(&Global, &alias))
+            .unwrap_or_else(|| self.fresh_dim_for(id, *span))
+            .clone()
+    }
+
+    /// `...` stands for any number of dimensions
+    fn fresh_dim_for(&mut self, id: &str, span: ByteSpan) -> Type {
+        if id == "..." {
+            self.fresh_dims(span)
+        } else {
+            self.fresh_dim(span)
+        }
+    }
+
+    /// evaluate dimension arithmetic. The result is a symbolic
+    /// dimension if any of the operands is not yet resolved
+    pub fn eval_dim(&mut self, mod_name: &ModName, term: &Term, span: &ByteSpan) -> Type {
+        match term {
+            Term::Integer(i, _) => Type::ResolvedDim(*i, *span),
+            Term::Ident(ref id, _) => self.resolve_dim_alias(mod_name, id, span),
+            Term::BinOp(op, lhs, rhs, _) => {
+                let lhs = self.eval_dim(mod_name, lhs, span);
+                let rhs = self.eval_dim(mod_name, rhs, span);
+                Type::dim_op(*op, lhs, rhs, *span)
+            }
+            _ => unimplemented!(),
+        }
     }
 
     /// generate a tensor from untyped ast tensor signature
     pub fn resolve_tensor(&mut self, mod_name: &ModName, t: &TensorTy) -> Type {
         match t {
-            TensorTy::Generic(ref dims, ref sp) => {
-                self.create_tensor(mod_name, &dims, sp)
+            TensorTy::Generic(ref dims, ref dtype, ref sp) => {
+                let dtype = self.resolve_dtype(*dtype, sp);
+                self.create_tensor(mod_name, &dims, dtype, sp)
             }
             TensorTy::Tensor(ref alias, ref sp) => {
                 self.resolve_type(mod_name, &Alias::Variable(alias.to_string()))
@@ -343,6 +520,10 @@ impl TypeEnv {
                     .unwrap()
                     .with_span(sp)
             }
+            TensorTy::Tuple(ref tys, ref sp) => Type::Tuple(
+                tys.iter().map(|t| self.resolve_tensor(mod_name, t)).collect(),
+                *sp,
+            ),
         }
     }
 
@@ -357,31 +538,39 @@ impl TypeEnv {
         match a {
             AliasAssign::Tensor {
                 ident: ref id,
-                rhs: TensorTy::Generic(ref tys, ref sp),
+                rhs: TensorTy::Generic(ref tys, ref dtype, ref sp),
                 ..
             } => {
-                self.add_tsr_alias(mod_name, &Alias::Variable(id.to_string()), tys, sp)
+                self.add_tsr_alias(mod_name, &Alias::Variable(id.to_string()), tys, *dtype, sp)
             }
             AliasAssign::Dimension {
                 ident: ref id,
-                rhs: Term::Integer(num, _),
+                ref rhs,
                 ref span,
             } => {
-                self.add_resolved_dim_alias(mod_name, &Alias::Variable(id.to_string()), *num, span)
+                self.add_resolved_dim_alias(mod_name, &Alias::Variable(id.to_string()), rhs, span)
             }
             _ => unimplemented!(),
         }
     }
 
     pub fn import_top_level_ty_sig(&mut self, mod_name: &ModName, ty_sig: &TensorTy) -> Result<(), Diag> {
-        if let TensorTy::Generic(dims, span) = ty_sig {
-            // first insert all the dims
-            for t in dims.iter().filter(|t| t.parse::<i64>().is_err()) {
-                let alias =  Alias::Variable(t.to_string());
-                if !self.exists(mod_name, &alias) {
-                    self.add_dim_alias(mod_name, &alias, *span)?;
+        match ty_sig {
+            TensorTy::Generic(dims, _, span) => {
+                // first insert all the dims
+                for t in dims.iter().flat_map(dim_aliases) {
+                    let alias =  Alias::Variable(t.to_string());
+                    if !self.exists(mod_name, &alias) && !self.exists(&Global, &alias) {
+                        self.add_dim_alias(mod_name, &alias, *span)?;
+                    }
+                }
+            }
+            TensorTy::Tuple(tys, _) => {
+                for t in tys {
+                    self.import_top_level_ty_sig(mod_name, t)?;
                 }
             }
+            TensorTy::Tensor(..) => (),
         }
 
         Ok(())
@@ -397,6 +586,11 @@ impl TypeEnv {
         self.current_mod = scp;
     }
 
+    /// whether `use` refers to a builtin module rather than a .trs file
+    pub fn is_core_path(&self, path_name: &str) -> bool {
+        self.core.borrow().has_path(path_name)
+    }
+
     /// import module type and associated methods into type environment
     pub fn import_module(&mut self, path_name: &str, mod_name: &str) -> Option<Result<(), Diag>> {
         let core = self.core.clone();
@@ -412,8 +606,16 @@ impl TypeEnv {
         .collect())
     }
 
+    /// optional parameters of a core op's method and their default values,
+    /// None if `mod_name` is not a core op
+    pub fn defaults(&self, mod_name: &str, fn_name: &str) -> Option<Vec<(&'static str, &'static str)>> {
+        self.core.borrow()
+            .find_mod(mod_name)
+            .map(|op| op.defaults(fn_name))
+    }
+
     pub fn import_prelude(&mut self) -> Result<(), Diag> {
-        for fun in &vec!["view"] {
+        for fun in &vec!["view", "to"] {
             self.add_type(&Global,
                 &Alias::Variable(fun.to_string()),
                 module!(fun.to_string())
@@ -462,3 +664,33 @@ impl TypeEnv {
         }
     }
 }
+
+/// names of the dimension aliases used in a tensor signature
+fn dim_aliases(term: &Term) -> Vec<String> {
+    match term {
+        Term::Ident(ref id, _) => vec![id.to_owned()],
+        Term::BinOp(_, lhs, rhs, _) => {
+            let mut ret = dim_aliases(lhs);
+            ret.extend(dim_aliases(rhs));
+            ret
+        }
+        _ => vec![],
+    }
+}
+
+/// constants are folded unless the arithmetic overflows or divides by zero
+fn check_folded(ty: &Type) -> Result<(), Diag> {
+    use parsing::term::Op;
+    match ty {
+        Type::DimExpr(op, l, r, sp) => {
+            match (op, r.as_num()) {
+                (Op::Div, Some(0)) | (Op::Mod, Some(0)) => return Err(Diag::DivisionByZero(ty.clone(), *sp)),
+                (_, Some(_)) if l.as_num().is_some() => return Err(Diag::DimOverflow(ty.clone(), *sp)),
+                _ => (),
+            }
+            check_folded(l)?;
+            check_folded(r)
+        }
+        _ => Ok(()),
+    }
+}
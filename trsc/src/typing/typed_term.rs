@@ -4,6 +4,7 @@ use codespan::ByteSpan;
 use span::CSpan;
 use std::collections::BTreeMap;
 use typing::type_env::Alias;
+use parsing::term::{Op, Pattern};
 use typing::Type;
 use std::fmt::Write;
 
@@ -14,6 +15,7 @@ pub enum TyTerm {
     TyProgram(Vec<TyDecl>),
     TyInteger(Type, i64, ByteSpan),
     TyFloat(Type, f64, ByteSpan),
+    TyBool(Type, bool, ByteSpan),
     TyList(Vec<TyTerm>),
     TyIdent(Type, Alias, ByteSpan),
     TyFieldAccess(TyFieldAccess),
@@ -26,6 +28,24 @@ pub enum TyTerm {
     },
     TyExpr(Box<TyTerm>, Type, ByteSpan),
     TyStmt(Box<TyTerm>, ByteSpan),
+    TyBinOp(Type, Op, Box<TyTerm>, Box<TyTerm>, ByteSpan),
+    TyLet(Type, Alias, Box<TyTerm>, ByteSpan),
+    TyLetTuple(Type, Vec<Pattern>, Box<TyTerm>, ByteSpan),
+    TyAssign(Type, Alias, Box<TyTerm>, ByteSpan),
+    TyForLoop {
+        var: Alias,
+        from: i64,
+        to: i64,
+        body: Box<TyTerm>,
+        span: ByteSpan,
+    },
+    TyConditional {
+        ty: Type,
+        cond: Box<TyTerm>,
+        then: Box<TyTerm>,
+        els: Box<TyTerm>,
+        span: ByteSpan,
+    },
 }
 
 impl TyTerm {
@@ -47,6 +67,26 @@ impl TyTerm {
         }
     }
 
+    pub fn as_bool(&self) -> Option<bool> {
+        use self::TyTerm::*;
+        match self {
+            TyBool(_, b, _) => Some(*b),
+            TyExpr(ref items, ..) => items.as_bool(),
+            _ => None,
+        }
+    }
+
+    /// whether the term applies a function somewhere inside it
+    pub fn is_nested(&self) -> bool {
+        use self::TyTerm::*;
+        match self {
+            TyFnApp(..) | TyBinOp(..) | TyBlock {..} | TyConditional {..} => true,
+            TyExpr(ref t, ..) => t.is_nested(),
+            TyTuple(_, ref ts, _) => ts.iter().any(|t| t.is_nested()),
+            _ => false,
+        }
+    }
+
     pub fn ty(&self) -> Type {
         use self::TyTerm::*;
         use self::Type::*;
@@ -55,6 +95,7 @@ impl TyTerm {
             TyProgram(_) => Unit(CSpan::fresh_span()),
             TyInteger(ref t, _, _) => t.clone(),
             TyFloat(ref t, _, _) => t.clone(),
+            TyBool(ref t, _, _) => t.clone(),
             TyList(_) => Unit(CSpan::fresh_span()),
             TyIdent(ref t, _, _) => t.clone(),
             TyFieldAccess(ref f_a) => f_a.ty(),
@@ -63,6 +104,12 @@ impl TyTerm {
             TyExpr(_,ref ty, _) => ty.clone(),
             TyStmt(..) => Unit(CSpan::fresh_span()),
             TyTuple(ref t, ..) => t.clone(),
+            TyBinOp(ref t, ..) => t.clone(),
+            TyLet(..) => Unit(CSpan::fresh_span()),
+            TyLetTuple(..) => Unit(CSpan::fresh_span()),
+            TyAssign(..) => Unit(CSpan::fresh_span()),
+            TyForLoop {..} => Unit(CSpan::fresh_span()),
+            TyConditional {ref ty, ..} => ty.clone(),
         }
     }
     pub fn span(&self) -> ByteSpan {
@@ -72,12 +119,20 @@ impl TyTerm {
             TyProgram(_) => CSpan::fresh_span(),
             TyInteger(_, _, ref s) => *s,
             TyFloat(_, _, ref s) => *s,
+            TyBool(_, _, ref s) => *s,
             TyIdent(_, _, ref s) => *s,
             TyFieldAccess(ref f_a) => f_a.span(),
             TyFnApp(ref f_a) => f_a.span(),
             TyBlock {ref span, ..} => *span,
             TyExpr(_, _, ref span) => *span,
             TyStmt(_, ref span) => *span,
+            TyTuple(_, _, ref span) => *span,
+            TyBinOp(_, _, _, _, ref span) => *span,
+            TyLet(_, _, _, ref span) => *span,
+            TyLetTuple(_, _, _, ref span) => *span,
+            TyAssign(_, _, _, ref span) => *span,
+            TyForLoop {ref span, ..} => *span,
+            TyConditional {ref span, ..} => *span,
             _ => panic!("{:?}", self),
         }
     }
@@ -88,8 +143,12 @@ impl TyTerm {
         match self {
             TyInteger(..) => write!(s, "{}", self.as_num()?).unwrap(),
             TyExpr(ref items, ..) => write!(s, "{}", items.as_str()?).unwrap(),
+            // dimension parameters of a generic node are passed by name
+            TyIdent(Type::DIM(..), ref alias, _) => write!(s, "{}", alias.as_str()).unwrap(),
             TyIdent(ref t, ..) => write!(s, "{}", t.as_string()).unwrap(),
             TyFloat(_, f, ..) => write!(s, "{}", f).unwrap(),
+            TyBool(_, true, ..) => write!(s, "True").unwrap(),
+            TyBool(_, false, ..) => write!(s, "False").unwrap(),
             TyTuple(_, ref ts, _) => {
                 write!(s, "(").unwrap();
                 write!(s, "{}", ts
@@ -149,7 +208,7 @@ pub enum TyAliasAssign {
 #[derive(Debug, PartialEq, Clone)]
 pub struct TyUseStmt {
     pub mod_name: String,
-    pub imported_names: Vec<String>,
+    pub imported_names: Vec<(String, String)>,
     pub span: ByteSpan,
 }
 
@@ -182,7 +241,11 @@ pub struct TyWeightsAssign {
     pub mod_name: String,
     pub fn_name: String,
     pub arg_ty: Type,
+    /// dimensions supplied to a generic node, named after its parameters
+    pub dim_args: Vec<TyFnAppArg>,
     pub fn_args: Vec<TyFnAppArg>,
+    /// length of the `nn.ModuleList` if declared in a `for` loop
+    pub count: Option<i64>,
     pub span: ByteSpan,
 }
 
@@ -194,12 +257,14 @@ pub struct TyFnApp {
     pub arg_ty: Type,
     pub ret_ty: Type,
     pub args: Vec<TyFnAppArg>,
+    /// index into a list of modules, `blocks[i]`
+    pub index: Option<Box<TyTerm>>,
     pub span: ByteSpan,
 }
 
 impl TyFnApp {
-    pub fn extend_arg(&mut self, arg: &TyFnAppArg) {
-        self.args.insert(0, arg.clone());
+    pub fn extend_arg(&mut self, arg: TyFnAppArg) {
+        self.args.insert(0, arg);
         let new_args_ty = self.args.to_ty(&self.span);
         // self.fn_ty = match &self.fn_ty {
         // Type::FUN(_, box r, span) => Type::FUN(box new_args_ty, box r.clone(), span),
@@ -207,6 +272,27 @@ impl TyFnApp {
         // };
         self.arg_ty = new_args_ty;
     }
+
+    /// the call without the applications nested in its arguments,
+    /// which keeps the literal keyword arguments the ops resolve on
+    pub fn shallow(&self) -> TyFnApp {
+        TyFnApp {
+            mod_name: self.mod_name.clone(),
+            orig_name: self.orig_name.clone(),
+            name: self.name.clone(),
+            arg_ty: self.arg_ty.clone(),
+            ret_ty: self.ret_ty.clone(),
+            args: self.args.iter()
+                .map(|a| TyFnAppArg {
+                    name: a.name.clone(),
+                    arg: if a.arg.is_nested() { box TyTerm::TyNone } else { a.arg.clone() },
+                    span: a.span,
+                })
+                .collect(),
+            index: self.index.clone(),
+            span: self.span,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
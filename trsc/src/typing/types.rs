@@ -5,6 +5,8 @@ use std::hash::{Hash, Hasher};
 use typing::type_env::TypeId;
 use std::collections::BTreeMap;
 use typing::type_env::ModName;
+use parsing::term::{DType, Op};
+use span::CSpan;
 
 #[derive(Clone, Eq, PartialOrd, Ord)]
 pub enum Type {
@@ -17,15 +19,22 @@ pub enum Type {
     // type variables that need to be resolved
     VAR(TypeId, ByteSpan),
     DIM(TypeId, ByteSpan),
+    /// variadic dimensions `...`, bound to a list of dimensions
+    DIMS(TypeId, ByteSpan),
     Tuple(Vec<Type>, ByteSpan),
 
     // recursive types
-    Module(String, Option<Box<Type>>, ByteSpan),
+    /// the forward type if known and the length of a list of modules
+    Module(String, Option<Box<Type>>, Option<i64>, ByteSpan),
     FnArgs(Vec<Type>, ByteSpan),
     FnArg(Option<String>, Box<Type>, ByteSpan),
     ResolvedDim(i64, ByteSpan),
+    /// symbolic dimension arithmetic over DIM and ResolvedDim
+    DimExpr(Op, Box<Type>, Box<Type>, ByteSpan),
     FUN(String, String, Box<Type>, Box<Type>, ByteSpan),
-    TSR(Vec<Type>, ByteSpan),
+    /// dimensions and element type, which is either `DTYPE` or a `VAR`
+    TSR(Vec<Type>, Box<Type>, ByteSpan),
+    DTYPE(DType, ByteSpan),
 }
 
 impl PartialEq for Type {
@@ -39,20 +48,30 @@ impl PartialEq for Type {
             // // UnresolvedModuleFun(_,_,_) => false,
             (VAR(a, _), VAR(b, _)) => a == b,
             (DIM(b, _), DIM(a, _)) => a == b,
-            (Module(a1, b1, _), Module(a2, b2, _)) => (a1 == a2) && (b1 == b2),
+            (DIMS(b, _), DIMS(a, _)) => a == b,
+            (Module(a1, b1, c1, _), Module(a2, b2, c2, _)) => (a1 == a2) && (b1 == b2) && (c1 == c2),
             (FnArgs(ta, _), FnArgs(tb, _)) => ta == tb,
             (Tuple(ta, _), Tuple(tb, _)) => ta == tb,
             (FnArg(n1, t1, _), FnArg(n2, t2, _)) => (n1 == n2) && (t1 == t2),
             (ResolvedDim(a, _), ResolvedDim(b, _)) => a == b,
+            (DimExpr(o1, l1, r1, _), DimExpr(o2, l2, r2, _)) => (o1 == o2) && (l1 == l2) && (r1 == r2),
             (FUN(m1, n1, p1, r1, _), FUN(m2, n2, p2, r2, _)) =>
                 (p1 == p2) && (r1 == r2) && (m1 == m2) && (n1 == n2),
-            (TSR(ts1, _), TSR(ts2, _)) => ts1 == ts2,
+            (TSR(ts1, dt1, _), TSR(ts2, dt2, _)) => (ts1 == ts2) && (dt1 == dt2),
+            (DTYPE(a, _), DTYPE(b, _)) => a == b,
             (UnresolvedModuleFun(a1, b1, c1, _), UnresolvedModuleFun(a2, b2, c2, _)) =>
                 (a1 == a2) && (b1 == b2) && (c1 == c2),
             (VAR(..), _) => false,
             (_, VAR(..)) => false,
-            (ResolvedDim(..), DIM(..)) => false,
-            (DIM(..), ResolvedDim(..)) => false,
+            // a dimension is never equal to a type of another kind
+            (DIM(..), _) => false,
+            (_, DIM(..)) => false,
+            (ResolvedDim(..), _) => false,
+            (_, ResolvedDim(..)) => false,
+            (DimExpr(..), _) => false,
+            (_, DimExpr(..)) => false,
+            (DIMS(..), _) => false,
+            (_, DIMS(..)) => false,
             _ => {
                 println!("Undefined comparison:");
                 println!("(1) {:?}", self);
@@ -81,10 +100,11 @@ impl Hash for Type {
                 b.hash(state)
             }
 
-            Module(a, b, _) => {
+            Module(a, b, c, _) => {
                 5.hash(state);
                 a.hash(state);
                 b.hash(state);
+                c.hash(state);
             }
             FnArgs(ts, _) => {
                 6.hash(state);
@@ -106,9 +126,10 @@ impl Hash for Type {
                 p.hash(state);
                 r.hash(state);
             }
-            TSR(ts, _) => {
+            TSR(ts, dt, _) => {
                 10.hash(state);
                 ts.hash(state);
+                dt.hash(state);
             }
             UnresolvedModuleFun(a, b, c, _) => {
                 11.hash(state);
@@ -116,6 +137,20 @@ impl Hash for Type {
                 b.hash(state);
                 c.hash(state);
             }
+            DimExpr(o, l, r, _) => {
+                12.hash(state);
+                o.hash(state);
+                l.hash(state);
+                r.hash(state);
+            }
+            DIMS(a, _) => {
+                13.hash(state);
+                a.hash(state)
+            }
+            DTYPE(a, _) => {
+                14.hash(state);
+                a.hash(state)
+            }
             // MismatchedDim(_,_) => true,
             _ => {
                 panic!("{:?}", self);
@@ -138,22 +173,33 @@ impl Type {
             // type variables that need to be resolved
             VAR(_, s) => *s,
             DIM(_, s) => *s,
+            DIMS(_, s) => *s,
             Tuple(_, s) => *s,
 
             // recursive types
-            Module(_, _, s) => *s,
+            Module(_, _, _, s) => *s,
             FnArgs(_, s) => *s,
             FnArg(_, _, s) => *s,
             ResolvedDim(_, s) => *s,
+            DimExpr(_, _, _, s) => *s,
             FUN(_, _, _, _, s) => *s,
-            TSR(_, s) => *s,
+            TSR(_, _, s) => *s,
+            DTYPE(_, s) => *s,
         }
     }
 
     pub fn as_vec(&self) -> Option<Vec<Type>> {
         use self::Type::TSR;
         match self {
-            TSR(ts, _) => Some(ts.to_owned()),
+            TSR(ts, _, _) => Some(ts.to_owned()),
+            _ => None,
+        }
+    }
+
+    /// element type of a tensor
+    pub fn dtype(&self) -> Option<Type> {
+        match self {
+            Type::TSR(_, dt, _) => Some(*dt.clone()),
             _ => None,
         }
     }
@@ -209,6 +255,7 @@ impl Type {
             Unit(_) => Unit(*sp),
             VAR(ref a, _) => VAR(*a, *sp),
             DIM(ref a, _) => DIM(*a, *sp),
+            DIMS(ref a, _) => DIMS(*a, *sp),
             INT(_) => INT(*sp),
             FLOAT(_) => FLOAT(*sp),
             BOOL(_) => BOOL(*sp),
@@ -216,13 +263,35 @@ impl Type {
             FnArgs(ref args, _) => FnArgs(args.clone(), *sp),
             FnArg(ref name, ref ty, _) => FnArg(name.clone(), ty.clone(), *sp),
             ResolvedDim(ref d, _) => ResolvedDim(*d, *sp),
-            Module(ref s, ref ty, _) => Module(s.clone(), ty.clone(), *sp),
+            DimExpr(ref o, ref l, ref r, _) => DimExpr(*o, l.clone(), r.clone(), *sp),
+            Module(ref s, ref ty, count, _) => Module(s.clone(), ty.clone(), *count, *sp),
             FUN(ref m,ref n,ref p, ref r, _) => FUN(m.clone(),n.clone(),p.clone(), r.clone(), *sp),
-            TSR(ref dims, _) => TSR(dims.clone(), *sp),
+            TSR(ref dims, ref dt, _) => TSR(dims.clone(), dt.clone(), *sp),
+            DTYPE(ref dt, _) => DTYPE(*dt, *sp),
             Tuple(ref vs, _) => Tuple(vs.clone(), *sp),
         }
     }
 
+    /// gives every part of the type that has no source location the span `sp`
+    pub fn fill_span(&self, sp: &ByteSpan) -> Type {
+        use self::Type::*;
+        let ty = match self {
+            FnArgs(args, s) => FnArgs(args.iter().map(|a| a.fill_span(sp)).collect(), *s),
+            FnArg(name, ty, s) => FnArg(name.clone(), box ty.fill_span(sp), *s),
+            DimExpr(o, l, r, s) => DimExpr(*o, box l.fill_span(sp), box r.fill_span(sp), *s),
+            Module(n, Some(ty), count, s) => Module(n.clone(), Some(box ty.fill_span(sp)), *count, *s),
+            FUN(m, n, p, r, s) => FUN(m.clone(), n.clone(), box p.fill_span(sp), box r.fill_span(sp), *s),
+            TSR(dims, dt, s) => TSR(dims.iter().map(|d| d.fill_span(sp)).collect(), box dt.fill_span(sp), *s),
+            Tuple(vs, s) => Tuple(vs.iter().map(|v| v.fill_span(sp)).collect(), *s),
+            _ => self.clone(),
+        };
+        if CSpan::is_fresh(&ty.span()) {
+            ty.with_span(sp)
+        } else {
+            ty
+        }
+    }
+
     pub fn as_mod_name(&self) -> ModName {
         match self {
             Type::Module(s,..) => ModName::Named(s.to_owned()),
@@ -233,9 +302,9 @@ impl Type {
     pub fn as_string(&self) -> String {
         use self::Type::*;
         match self {
-            Module(ref n, _, _) => n.to_owned(),
-            TSR(tys, _) => tys.iter().map(|t| t.as_string()).collect::<Vec<_>>().join(", "),
-            DIM(_, _) => "-1".to_owned(),
+            Module(ref n, ..) => n.to_owned(),
+            TSR(tys, _, _) => tys.iter().map(|t| t.as_string()).collect::<Vec<_>>().join(", "),
+            DIM(_, _) | DimExpr(..) => "-1".to_owned(),
             ResolvedDim(i, _) => format!("{}", i),
             _ => panic!("{:?}", self),
         }
@@ -249,10 +318,73 @@ impl Type {
         }
     }
 
+    /// numeric values that broadcast against a tensor
+    pub fn is_scalar(&self) -> bool {
+        use self::Type::*;
+        match self {
+            INT(..) | FLOAT(..) | ResolvedDim(..) | DimExpr(..) => true,
+            _ => false,
+        }
+    }
+
+    /// build a dimension from arithmetic on two dimensions.
+    /// Constants are folded and kept on the right hand side so that
+    /// equivalent expressions such as `(H - 4) / 2 - 4` and `(H - 6) / 2 - 3`
+    /// share the same form `H / 2 - 6`.
+    pub fn dim_op(op: Op, lhs: Type, rhs: Type, sp: ByteSpan) -> Type {
+        use self::Type::*;
+        match (op, lhs.as_num(), rhs.as_num()) {
+            (_, Some(a), Some(b)) => match op.eval(a, b) {
+                Some(n) => ResolvedDim(n, sp),
+                None => DimExpr(op, box lhs, box rhs, sp),
+            },
+            (Op::Sub, _, Some(c)) if c != i64::min_value() => Type::dim_op(Op::Add, lhs, ResolvedDim(-c, sp), sp),
+            (Op::Add, Some(_), _) | (Op::Mult, Some(_), _) => Type::dim_op(op, rhs, lhs, sp),
+            (Op::Add, _, Some(0)) | (Op::Mult, _, Some(1)) | (Op::Div, _, Some(1)) => lhs,
+            (Op::Add, _, Some(c)) | (Op::Mult, _, Some(c)) => {
+                // an overflowing constant is left unfolded
+                let folded = match lhs {
+                    DimExpr(op2, ref x, box ResolvedDim(c2, _), _) if op2 == op => {
+                        op.eval(c2, c).map(|c| (*x.clone(), c))
+                    }
+                    _ => None,
+                };
+                match folded {
+                    Some((x, c)) => Type::dim_op(op, x, ResolvedDim(c, sp), sp),
+                    None => DimExpr(op, box lhs, box rhs, sp),
+                }
+            }
+            (Op::Div, _, Some(d)) if d > 0 => match lhs {
+                // (x + c) / d == (x + r) / d + q where c = q * d + r
+                DimExpr(Op::Add, ref x, box ResolvedDim(c, _), _) if c < 0 || c >= d => {
+                    let q = if c % d < 0 { c / d - 1 } else { c / d };
+                    let r = (c % d + d) % d;
+                    let x = Type::dim_op(Op::Add, *x.clone(), ResolvedDim(r, sp), sp);
+                    let x = Type::dim_op(Op::Div, x, ResolvedDim(d, sp), sp);
+                    Type::dim_op(Op::Add, x, ResolvedDim(q, sp), sp)
+                }
+                // x / a / b == x / (a * b)
+                DimExpr(Op::Div, ref x, box ResolvedDim(d2, _), _) if d2 > 0 && d.checked_mul(d2).is_some() => {
+                    Type::dim_op(Op::Div, *x.clone(), ResolvedDim(d * d2, sp), sp)
+                }
+                _ => DimExpr(op, box lhs, box rhs, sp),
+            },
+            _ => DimExpr(op, box lhs, box rhs, sp),
+        }
+    }
+
+    /// whether the tensor has variadic dimensions `[..., C]`
+    pub fn is_variadic(&self) -> bool {
+        match self {
+            Type::TSR(ref dims, _, _) => dims.iter().any(|d| if let Type::DIMS(..) = d { true } else { false }),
+            _ => false,
+        }
+    }
+
     pub fn as_rank(&self) -> usize {
         use self::Type::*;
         match self {
-            TSR(ref i, _) => i.len(),
+            TSR(ref i, _, _) => i.len(),
             _ => unimplemented!(),
         }
     }
@@ -268,14 +400,19 @@ impl Type {
 
             VAR(..) => false,
             DIM(..) => false,
+            DIMS(..) => false,
+            DimExpr(..) => false,
 
-            Module(_, Some(i), _) => i.is_resolved(),
-            Module(_, None, _) => false,
+            Module(_, Some(i), ..) => i.is_resolved(),
+            Module(_, None, ..) => false,
             FnArgs(ts, _) => ts.iter().map(|t| t.is_resolved()).all(|t| t),
             FnArg(_, t, _) => t.is_resolved(),
             ResolvedDim(_, _) => true,
             FUN(_,_, p, r, _) => Type::is_resolved(p) && r.is_resolved(),
-            TSR(_ts, _) => true, //ts.iter().map(|t| t.is_resolved()).all(|t|t),
+            // the rank is unknown until the ellipsis is bound,
+            // the element type does not affect the shape
+            TSR(..) => !self.is_variadic(),
+            DTYPE(..) => true,
             _ => unimplemented!(),
         }
     }
@@ -292,25 +429,44 @@ impl Debug for Type {
             UnresolvedModuleFun(ref a, ref b, ref c, _) => {
                 write!(f, "UNRESOLVED({}::{}::{})", a, b, c)
             }
-            Tuple(ref tys, _) => write!(f, "({:?})", tys),
+            Tuple(ref tys, _) => {
+                write!(f, "(")?;
+                for (i, ty) in tys.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}", ty)?;
+                }
+                write!(f, ")")
+            }
             VAR(ref t_id, _) => write!(f, "'{:?}", t_id),
             DIM(ref t_id, _) => write!(f, "!{:?}", t_id),
+            DIMS(ref t_id, _) => write!(f, "...{:?}", t_id),
             FnArgs(ref args, _) => write!(f, "FnArgs({:?})", args),
             FnArg(ref name, ref ty, _) => write!(f, "ARG({:?}={:?})", name, ty),
             ResolvedDim(ref d, _) => write!(f, "<{}>", d),
-            Module(ref s, ref ty, _) => write!(f, "MODULE({}, {:?})", s, ty),
+            DimExpr(Op::Add, ref l, box ResolvedDim(ref c, _), _) if *c < 0 => write!(f, "({:?} - {})", l, -c),
+            DimExpr(ref o, ref l, box ResolvedDim(c, _), _) => write!(f, "({:?} {} {})", l, o.as_str(), c),
+            DimExpr(ref o, ref l, ref r, _) => write!(f, "({:?} {} {:?})", l, o.as_str(), r),
+            Module(ref s, ref ty, _, _) => write!(f, "MODULE({}, {:?})", s, ty),
             FUN(ref module, ref name,ref p, ref r, _) => write!(f, "{}::{}({:?} -> {:?})", module,name,p, r),
-            TSR(ref dims, _) => {
+            TSR(ref dims, ref dt, _) => {
                 if !dims.is_empty() {
                     write!(f, "[")?;
                     for i in dims[0..dims.len() - 1].iter() {
                         write!(f, "{:?}, ", i)?;
                     }
-                    write!(f, "{:?}]", dims[dims.len() - 1])
+                    write!(f, "{:?}]", dims[dims.len() - 1])?;
                 } else {
-                    write!(f, "[]")
+                    write!(f, "[]")?;
+                }
+                // `f32` is the default and is left out
+                match **dt {
+                    DTYPE(DType::F32, _) | VAR(..) => Ok(()),
+                    ref dt => write!(f, ": {:?}", dt),
                 }
             }
+            DTYPE(ref dt, _) => write!(f, "{}", dt.as_str()),
         }
     }
 }
@@ -342,8 +498,14 @@ macro_rules! float {
 }
 
 macro_rules! tsr {
-    ($tsr:expr) => {
-        Type::TSR($tsr, CSpan::fresh_span())
+    ($tsr:expr, $dtype:expr) => {
+        Type::TSR($tsr, box $dtype, CSpan::fresh_span())
+    };
+}
+
+macro_rules! dtype {
+    ($dtype:ident) => {
+        Type::DTYPE(::parsing::term::DType::$dtype, CSpan::fresh_span())
     };
 }
 
@@ -367,7 +529,7 @@ macro_rules! tuple {
 
 macro_rules! module {
     ($e1:expr) => {
-        Type::Module($e1.to_owned(), None, CSpan::fresh_span())
+        Type::Module($e1.to_owned(), None, None, CSpan::fresh_span())
     };
 }
 
@@ -387,4 +549,20 @@ mod tests {
         );
         assert_eq!(h.len(), 2);
     }
+    #[test]
+    fn should_share_form_of_equivalent_dims() {
+        let sp = Span::new(ByteIndex(1), ByteIndex(1));
+        let h = Type::DIM(1, sp);
+        let dim = |n| Type::ResolvedDim(n, sp);
+        // (H - 4) / 2 - 4
+        let a = Type::dim_op(Op::Sub, h.clone(), dim(4), sp);
+        let a = Type::dim_op(Op::Div, a, dim(2), sp);
+        let a = Type::dim_op(Op::Sub, a, dim(4), sp);
+        // (H - 6) / 2 - 3
+        let b = Type::dim_op(Op::Sub, h.clone(), dim(6), sp);
+        let b = Type::dim_op(Op::Div, b, dim(2), sp);
+        let b = Type::dim_op(Op::Sub, b, dim(3), sp);
+        assert_eq!(a, b);
+        assert_eq!(Type::dim_op(Op::Mult, dim(4), dim(8), sp), dim(32));
+    }
 }
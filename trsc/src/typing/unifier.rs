@@ -1,17 +1,52 @@
 use typing::{Type, TypeEnv};
-use typing::type_env::TypeId;
 use span::CSpan;
 use errors::{Emitter, Diag };
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::process::exit;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::mem::{self, discriminant};
 
-use typing::constraint::{Constraints, Equals};
+use typing::constraint::{Constraints, Deferred, Equals};
 
 pub struct Unifier {
     pub emitter: Rc<RefCell<Emitter>>,
     pub tenv: Rc<RefCell<TypeEnv>>,
+    table: Table,
+    /// equations left to unify, the last one is unified first
+    work: Vec<Equals>,
+    /// equations that can't be solved until more variables are bound
+    postponed: Vec<Equals>,
+    /// equations unified and deferred constraints retried so far
+    steps: usize,
+}
+
+/// Union-find table over type variables `VAR`, `DIM` and `DIMS`.
+/// Equal variables form a class whose root is the oldest variable,
+/// and the root of a class may be bound to a type that is not a variable.
+#[derive(Debug, Default)]
+struct Table {
+    parent: HashMap<Type, Type>,
+    bound: HashMap<Type, Type>,
+}
+
+impl Table {
+    /// root of the class of `var`, with path compression
+    fn find(&mut self, var: &Type) -> Type {
+        let mut root = var.clone();
+        while let Some(p) = self.parent.get(&root) {
+            root = p.clone();
+        }
+        let mut v = var.clone();
+        while v != root {
+            v = self.parent.insert(v, root.clone()).unwrap();
+        }
+        root
+    }
+
+    fn vars(&self) -> Vec<Type> {
+        self.parent.keys().chain(self.bound.keys()).cloned().collect()
+    }
 }
 
 impl Unifier {
@@ -20,145 +55,273 @@ impl Unifier {
         Unifier {
             emitter,
             tenv,
+            table: Table::default(),
+            work: vec![],
+            postponed: vec![],
+            steps: 0,
+        }
+    }
+
+    /// Worklist over the deferred constraints. After the constraints are
+    /// unified, a deferred constraint is retried only if the bindings so far
+    /// refined its inputs, and the equations it generates are unified right
+    /// away so the constraints after it in a pipeline see its output.
+    /// Whatever is left once nothing changes can't be inferred.
+    pub fn solve(&mut self, cs: Constraints) -> Substitution {
+        let mut deferred = cs.deferred.clone();
+        let mut failed = false;
+        self.unify_eqs(cs.set);
+        loop {
+            let mut eqs = vec![];
+            let mut resolved_any = false;
+
+            for eq in mem::replace(&mut self.postponed, vec![]) {
+                let applied = self.resolve_eq(&eq);
+                if applied == eq {
+                    self.postponed.push(eq);
+                } else {
+                    eqs.push(applied);
+                }
+            }
+
+            for d in mem::replace(&mut deferred, vec![]) {
+                self.steps += 1;
+                let applied = self.resolve_deferred(&d);
+                if applied.inputs() == d.inputs() {
+                    deferred.push(d);
+                    continue;
+                }
+                match applied.resolve(&self.tenv) {
+                    Ok(Some(resolved)) => {
+                        resolved_any = true;
+                        self.unify_eqs(resolved);
+                    }
+                    Ok(None) => deferred.push(applied),
+                    Err(e) => {
+                        failed = true;
+                        self.emitter.borrow_mut().add(e);
+                    }
+                }
+            }
+
+            if eqs.is_empty() && !resolved_any {
+                break;
+            }
+            self.unify_eqs(eqs);
+        }
+
+        // shapes downstream of a failed op can't be inferred either
+        for d in deferred.into_iter().filter(|_| !failed) {
+            let (name, input, span) = d.describe();
+            self.emitter.borrow_mut().add(Diag::UnresolvedShape(name, input, span));
+        }
+        self.substitution()
+    }
+
+    /// every variable seen so far mapped to its fully resolved type
+    fn substitution(&mut self) -> Substitution {
+        let span = CSpan::fresh_span();
+        let mut ret = BTreeMap::new();
+        for var in self.table.vars() {
+            let ty = self.resolve(&var).with_span(&span);
+            ret.insert(var.with_span(&span), ty);
+        }
+        Substitution(ret)
+    }
+
+    fn unify_eqs<I: IntoIterator<Item=Equals>>(&mut self, eqs: I) {
+        let mut eqs: Vec<_> = eqs.into_iter().collect();
+        eqs.reverse();
+        let depth = self.work.len();
+        self.work.extend(eqs);
+        while self.work.len() > depth {
+            let Equals(a, b) = self.work.pop().unwrap();
+            self.steps += 1;
+            let eq = Equals(self.resolve(&a), self.resolve(&b));
+            self.unify_one(eq);
+        }
+    }
+
+    /// unified before the rest of the worklist
+    fn push<I: IntoIterator<Item=Equals>>(&mut self, eqs: I) {
+        let mut eqs: Vec<_> = eqs.into_iter().collect();
+        eqs.reverse();
+        self.work.extend(eqs);
+    }
+
+    /// replace every bound variable in `ty`
+    fn resolve(&mut self, ty: &Type) -> Type {
+        use self::Type::*;
+        match ty {
+            VAR(_, sp) | DIM(_, sp) | DIMS(_, sp) => {
+                let root = self.table.find(ty);
+                match self.table.bound.get(&root).cloned() {
+                    Some(bound) => {
+                        let bound = self.resolve(&bound);
+                        self.table.bound.insert(root, bound.clone());
+                        // point at where the type came from if it is known
+                        if CSpan::is_fresh(&bound.span()) {
+                            bound.with_span(sp)
+                        } else {
+                            bound
+                        }
+                    }
+                    None => root.with_span(sp),
+                }
+            }
+            DimExpr(op, l, r, s) => {
+                let l = self.resolve(l);
+                let r = self.resolve(r);
+                Type::dim_op(*op, l, r, *s)
+            }
+            FnArgs(args, s) => FnArgs(args.iter().map(|a| self.resolve(a)).collect(), *s),
+            FnArg(name, a, s) => FnArg(name.clone(), box self.resolve(a), *s),
+            Tuple(tys, s) => Tuple(tys.iter().map(|t| self.resolve(t)).collect(), *s),
+            FUN(m, n, p, r, s) => FUN(m.clone(), n.clone(), box self.resolve(p), box self.resolve(r), *s),
+            // a bound ellipsis is spliced into the surrounding tensor
+            TSR(dims, dt, s) => {
+                let mut resolved = vec![];
+                for d in dims {
+                    match self.resolve(d) {
+                        TSR(ds, _, _) => resolved.extend(ds),
+                        d => resolved.push(d),
+                    }
+                }
+                TSR(resolved, box self.resolve(dt), *s)
+            }
+            Module(n, Some(ty), count, s) => Module(n.clone(), Some(box self.resolve(ty)), *count, *s),
+            _ => ty.clone(),
         }
     }
 
-    pub fn unify(&mut self, cs: Constraints) -> Substitution {
-        if cs.is_empty() {
-            Substitution::empty()
-        } else {
-            let emitter = cs.emitter.clone();
-            let tenv = cs.tenv.clone();
-            let mut it = cs.set.into_iter();
-            let mut subst = self.unify_one(it.next().unwrap());
-            let subst_tail = subst.apply(&Constraints {set: it.collect(), emitter, tenv});
-            let subst_tail: Substitution = self.unify(subst_tail);
-            subst.compose(subst_tail)
+    fn resolve_eq(&mut self, eq: &Equals) -> Equals {
+        let Equals(a, b) = eq;
+        Equals(self.resolve(a), self.resolve(b))
+    }
+
+    fn resolve_deferred(&mut self, d: &Deferred) -> Deferred {
+        match d {
+            Deferred::Op { ty, mod_name, fn_app, inits } => {
+                let mut fn_app = fn_app.clone();
+                fn_app.arg_ty = self.resolve(&fn_app.arg_ty);
+                fn_app.ret_ty = self.resolve(&fn_app.ret_ty);
+                Deferred::Op {
+                    ty: ty.clone(),
+                    mod_name: mod_name.clone(),
+                    fn_app,
+                    inits: inits.clone(),
+                }
+            }
+            Deferred::BinOp(ty, lhs, rhs, sp) =>
+                Deferred::BinOp(self.resolve(ty), self.resolve(lhs), self.resolve(rhs), *sp),
         }
     }
 
-    fn unify_one(&mut self, eq: Equals) -> Substitution {
+    /// both sides are resolved, sub-equations are pushed onto the worklist
+    fn unify_one(&mut self, eq: Equals) {
         use self::Type::*;
         // println!("{:?}", eq);
-        let emitter = Rc::clone(&self.emitter);
-        let tenv = Rc::clone(&self.tenv);
         match eq {
-            Equals(Unit(_), Unit(_)) => Substitution::empty(),
-            Equals(INT(_), INT(_)) => Substitution::empty(),
-            Equals(FLOAT(_), FLOAT(_)) => Substitution::empty(),
-            Equals(BOOL(_), BOOL(_)) => Substitution::empty(),
+            Equals(Unit(_), Unit(_)) => (),
+            Equals(INT(_), INT(_)) => (),
+            Equals(FLOAT(_), FLOAT(_)) => (),
+            Equals(BOOL(_), BOOL(_)) => (),
 
-            Equals(INT(_), ResolvedDim(_, _)) => Substitution::empty(),
-            Equals(ResolvedDim(_, _), INT(_)) => Substitution::empty(),
+            Equals(INT(_), ResolvedDim(_, _)) => (),
+            Equals(ResolvedDim(_, _), INT(_)) => (),
+            // a dimension parameter can be passed where an int is expected
+            Equals(INT(_), DIM(..)) => (),
+            Equals(DIM(..), INT(_)) => (),
 
             Equals(a @ ResolvedDim(_, _), b @ ResolvedDim(_, _)) => {
-                if a.as_num() == b.as_num() {
-                    Substitution::empty()
-                } else {
+                if a.as_num() != b.as_num() {
                     self.emitter.borrow_mut().add(Diag::DimensionMismatch(a.clone(), b.clone()));
-                    Substitution::empty()
                 }
             }
 
-            Equals(VAR(tvar, _), ty) => self.unify_var(tvar, ty),
-            Equals(ty, VAR(tvar, _)) => self.unify_var(tvar, ty),
+            Equals(v @ VAR(..), ty) => self.unify_var(v, ty),
+            Equals(ty, v @ VAR(..)) => self.unify_var(v, ty),
+
+            Equals(a @ DimExpr(..), b) => self.unify_dims(a, b),
+            Equals(a, b @ DimExpr(..)) => self.unify_dims(a, b),
 
-            Equals(DIM(tvar, _), ty) => self.unify_var(tvar, ty),
-            Equals(ty, DIM(tvar, _)) => self.unify_var(tvar, ty),
+            Equals(v @ DIM(..), ty) => self.unify_var(v, ty),
+            Equals(ty, v @ DIM(..)) => self.unify_var(v, ty),
 
-            Equals(FnArgs(v1, _), FnArgs(v2, _)) => self.unify(
-                Constraints {
-                    set: v1.into_iter().zip(v2).map(|(i, j)| Equals(i, j)).collect(),
-                    emitter,
-                    tenv,
-                },
-            ),
+            Equals(v @ DIMS(..), ty) => self.unify_var(v, ty),
+            Equals(ty, v @ DIMS(..)) => self.unify_var(v, ty),
 
-            Equals(FnArg(Some(a), ty1, _), FnArg(Some(b), ty2, _)) => {
+            Equals(FnArgs(v1, _), FnArgs(v2, _)) => self.unify_args(v1, v2),
+
+            Equals(FnArg(Some(a), ty1, _), FnArg(Some(b), ty2, sp)) => {
                 if a == b {
-                    self.unify(
-                        Constraints {
-                            set: btreeset!{ Equals(*ty1, *ty2)},
-                            emitter,
-                            tenv,
-                        },
-                        )
+                    self.push(vec![Equals(*ty1, *ty2)])
                 } else {
-                    panic!("supplied parameter is incorrect! {} != {}", a, b);
+                    self.emitter.borrow_mut().add(Diag::WrongArgName(a, b, sp));
                 }
             }
 
-            Equals(FUN(m1,n1,p1, r1, _), FUN(m2,n2,p2, r2, _)) => {
+            Equals(FUN(m1,n1,p1, r1, s1), FUN(m2,n2,p2, r2, s2)) => {
                 if n1 == n2 {
-                    self.unify(
-                        Constraints{
-                            set: btreeset!{
-                                Equals(*p1, *p2),
-                                Equals(*r1, *r2),
-                            },
-                            emitter,
-                            tenv,
-                        },
-                    )
+                    self.push(vec![
+                        Equals(*p1, *p2),
+                        Equals(*r1, *r2),
+                    ])
                 } else {
-                    println!("{} {} {} {}", m1, m2, n1, n2);
-                    panic!()
+                    self.emitter.borrow_mut().add(Diag::TypeError(FUN(m1,n1,p1,r1,s1), FUN(m2,n2,p2,r2,s2)));
                 }
             },
 
-            Equals(Tuple(vs1, _), Tuple(vs2, _)) => self.unify(
-                Constraints {
-                    set: vs1.into_iter().zip(vs2).map(|(i,j)| Equals(i,j)).collect(),
-                    emitter,
-                    tenv,
-                },
-            ),
-
-            Equals(ts1 @ TSR(_, _), ts2 @ TSR(_, _)) => {
-                if ts1.as_rank() == ts2.as_rank() {
-                    if let (TSR(dims1, s1), TSR(dims2, s2)) = (ts1.clone(), ts2.clone()) {
-                        let cons = Constraints {
-                            set: dims1
-                                .into_iter()
-                                .zip(dims2)
-                                .filter_map(|(i, j)| {
-                                    if let (Type::ResolvedDim(a,_), Type::ResolvedDim(b,_)) = (i.clone(),j.clone()) {
-                                        if a != b { self.emitter.borrow_mut().add(Diag::TypeError(ts1.clone(),ts2.clone())) }
-                                        None
-                                    } else {
-                                        Some(Equals(i.with_span(&s1), j.with_span(&s2)))
-                                    }
-                                })
-                                .collect(),
-                            emitter,
-                            tenv,
-                        };
-                        self.unify(cons)
+            Equals(Tuple(vs1, s1), Tuple(vs2, s2)) => {
+                if vs1.len() == vs2.len() {
+                    self.push(vs1.into_iter().zip(vs2).map(|(i,j)| Equals(i,j)))
+                } else {
+                    self.emitter.borrow_mut().add(Diag::TypeError(Tuple(vs1, s1), Tuple(vs2, s2)));
+                }
+            }
+
+            // a single int stands for a tuple of them, e.g. `kernel_size=5`
+            Equals(Tuple(ref vs, _), INT(_)) | Equals(INT(_), Tuple(ref vs, _))
+                if all_ints(vs) => (),
+
+            Equals(ts1 @ TSR(..), ts2 @ TSR(..)) => {
+                if ts1.is_variadic() || ts2.is_variadic() {
+                    self.unify_variadic(ts1, ts2)
+                } else if ts1.as_rank() == ts2.as_rank() {
+                    let dtype_eq = self.unify_dtypes(&ts1, &ts2);
+                    if let (TSR(dims1, _, s1), TSR(dims2, _, s2)) = (ts1.clone(), ts2.clone()) {
+                        let eqs: Vec<_> = dims1
+                            .into_iter()
+                            .zip(dims2)
+                            .filter_map(|(i, j)| {
+                                if let (Type::ResolvedDim(a,_), Type::ResolvedDim(b,_)) = (i.clone(),j.clone()) {
+                                    if a != b { self.emitter.borrow_mut().add(Diag::TypeError(ts1.clone(),ts2.clone())) }
+                                    None
+                                } else {
+                                    Some(Equals(i.with_span(&s1), j.with_span(&s2)))
+                                }
+                            })
+                            .chain(dtype_eq)
+                            .collect();
+                        self.push(eqs)
                     } else {
                         unimplemented!();
                     }
                 } else {
                     self.emitter.borrow_mut().add(Diag::RankMismatch(ts1, ts2));
-                    Substitution::empty()
                 }
             }
 
-            Equals(Module(n1, Some(box ty1), _), Module(n2, Some(box ty2), _)) => self.unify(
-                Constraints {
-                    set: btreeset!{
-                        if n1 == n2 {
-                            Equals(ty1, ty2)
-                        } else {
-                            panic!();
-                        }
-                    },
-                    emitter,
-                    tenv,
-                },
-            ),
-
-            Equals(u @ UnresolvedModuleFun(_, _, _, _), ty) => {
-                Substitution::empty()
+            Equals(DTYPE(a, _), DTYPE(b, _)) if a == b => (),
+
+            Equals(m1 @ Module(_, Some(_), ..), m2 @ Module(_, Some(_), ..)) => {
+                if let (Module(n1, Some(box ty1), ..), Module(n2, Some(box ty2), ..)) = (m1.clone(), m2.clone()) {
+                    if n1 == n2 {
+                        self.push(vec![Equals(ty1, ty2)])
+                    } else {
+                        self.emitter.borrow_mut().add(Diag::TypeError(m1, m2));
+                    }
+                }
             }
 
             _ => {
@@ -171,93 +334,266 @@ impl Unifier {
         }
     }
 
-    fn unify_var(&mut self, tvar: TypeId, ty: Type) -> Substitution {
-        use self::Type::*;
+    /// keyword arguments are matched by name so they can be supplied in any
+    /// order and optional parameters can be left out
+    fn unify_args(&mut self, params: Vec<Type>, args: Vec<Type>) {
+        let name = |t: &Type| if let Type::FnArg(Some(n), ..) = t { Some(n.clone()) } else { None };
+        if params.iter().chain(&args).any(|t| name(t).is_none()) {
+            return self.push(params.into_iter().zip(args).map(|(i, j)| Equals(i, j)));
+        }
+        let supplied: Vec<_> = args.iter().map(|a| name(a)).collect();
+        let mut eqs = vec![];
+        for arg in args {
+            match params.iter().find(|p| name(p) == name(&arg)) {
+                Some(p) => eqs.push(Equals(p.clone(), arg)),
+                // most likely a misspelling of a parameter that wasn't supplied,
+                // extra arguments an op doesn't type such as `p` of `leaky_relu` are fine
+                None => {
+                    let expected = params.iter()
+                        .map(|p| name(p))
+                        .find(|p| !supplied.contains(p));
+                    if let Some(Some(expected)) = expected {
+                        let e = Diag::WrongArgName(expected, name(&arg).unwrap(), arg.span());
+                        self.emitter.borrow_mut().add(e);
+                    }
+                }
+            }
+        }
+        self.push(eqs)
+    }
 
-        let span = CSpan::fresh_span();
-        match ty.clone() {
-            VAR(tvar2, _) => {
-                if tvar == tvar2 {
-                    Substitution::empty()
-                } else {
-                    Substitution(btreemap!{ VAR(tvar, span) => ty })
+    /// element types of two tensors are either both known and the same,
+    /// or the unknown one is bound to the other
+    fn unify_dtypes(&mut self, ts1: &Type, ts2: &Type) -> Option<Equals> {
+        match (ts1.dtype()?, ts2.dtype()?) {
+            (Type::DTYPE(a, _), Type::DTYPE(b, _)) => {
+                if a != b {
+                    self.emitter.borrow_mut().add(Diag::DTypeMismatch(ts1.clone(), ts2.clone()));
                 }
+                None
+            }
+            (a, b) => Some(Equals(a, b)),
+        }
+    }
+
+    /// `[?, ..., C]` against a tensor: the fixed dimensions on both ends are
+    /// equated pairwise and the ellipsis is bound to whatever is left over
+    fn unify_variadic(&mut self, ts1: Type, ts2: Type) {
+        use self::Type::*;
+        let is_dims = |t: &Type| if let DIMS(..) = t { true } else { false };
+        let (mut dims1, mut dims2) = (ts1.as_vec().unwrap(), ts2.as_vec().unwrap());
+        let mut eqs = vec![];
+        // leading dimensions
+        while !dims1.is_empty() && !dims2.is_empty() && !is_dims(&dims1[0]) && !is_dims(&dims2[0]) {
+            eqs.push(Equals(dims1.remove(0), dims2.remove(0)));
+        }
+        // trailing dimensions
+        loop {
+            match (dims1.last(), dims2.last()) {
+                (Some(a), Some(b)) if !is_dims(a) && !is_dims(b) => (),
+                _ => break,
+            }
+            eqs.push(Equals(dims1.pop().unwrap(), dims2.pop().unwrap()));
+        }
+        match (dims1.as_slice(), dims2.as_slice()) {
+            ([a], [b]) if a == b => (),
+            ([v], rest) if is_dims(v) => {
+                eqs.push(Equals(v.clone(), tsr!(rest.to_vec(), ts2.dtype().unwrap()).with_span(&ts2.span())));
+            }
+            (rest, [v]) if is_dims(v) => {
+                eqs.push(Equals(v.clone(), tsr!(rest.to_vec(), ts1.dtype().unwrap()).with_span(&ts1.span())));
             }
-            DIM(tvar2, _) => {
-                if tvar == tvar2 {
-                    Substitution::empty()
+            ([], _) | (_, []) => {
+                self.emitter.borrow_mut().add(Diag::RankMismatch(ts1, ts2));
+                return;
+            }
+            // ellipses on both sides
+            (rest1, rest2) => self.postponed.push(Equals(
+                tsr!(rest1.to_vec(), ts1.dtype().unwrap()).with_span(&ts1.span()),
+                tsr!(rest2.to_vec(), ts2.dtype().unwrap()).with_span(&ts2.span()),
+            )),
+        }
+        eqs.extend(self.unify_dtypes(&ts1, &ts2));
+        eqs.sort();
+        eqs.dedup();
+        self.push(eqs)
+    }
+
+    /// `var` is an unbound type variable `VAR`, `DIM` or `DIMS`, and `ty` is resolved
+    fn unify_var(&mut self, var: Type, ty: Type) {
+        use self::Type::*;
+        let var = self.table.find(&var);
+        match ty {
+            VAR(..) | DIM(..) | DIMS(..) => {
+                let ty = self.table.find(&ty);
+                if var == ty {
+                } else if discriminant(&var) == discriminant(&ty) && var < ty {
+                    // the older variable stays the root of the class
+                    self.table.parent.insert(ty, var);
                 } else {
-                    Substitution(btreemap!{ VAR(tvar, span) => ty })
+                    self.table.parent.insert(var, ty);
                 }
             }
-            _ => if occurs(tvar, &ty) {
-                panic!("circular type")
+            _ => if occurs(&var, &ty) {
+                self.emitter.borrow_mut().add(Diag::CircularType(var, ty));
             } else {
-                Substitution(btreemap!{ VAR(tvar, span) => ty })
+                self.table.bound.insert(var, ty);
             },
         }
     }
+
+    /// equate two dimensions where at least one is symbolic.
+    /// The equation is solved if it is linear in a single unknown dimension,
+    /// otherwise it is postponed until more dimensions are known
+    fn unify_dims(&mut self, a: Type, b: Type) {
+        use self::Type::*;
+        match (&a, &b) {
+            (INT(_), _) | (_, INT(_)) => return,
+            (DIM(..), _) if !occurs(&a, &b) => return self.unify_var(a.clone(), b.clone()),
+            (_, DIM(..)) if !occurs(&b, &a) => return self.unify_var(b.clone(), a.clone()),
+            _ => (),
+        }
+
+        let (lhs, rhs) = match (linear_form(&a), linear_form(&b)) {
+            (Some(Ok(lhs)), Some(Ok(rhs))) => (lhs, rhs),
+            (Some(Err(e)), _) | (_, Some(Err(e))) => {
+                self.emitter.borrow_mut().add(e);
+                return;
+            }
+            _ => {
+                self.emitter.borrow_mut().add(Diag::TypeError(a, b));
+                return;
+            }
+        };
+
+        // move everything to the left hand side: sum(coef * term) + c = 0
+        let LinearForm(mut terms, c) = lhs;
+        let LinearForm(rhs_terms, rhs_c) = rhs;
+        let moved = rhs_terms.into_iter()
+            .try_fold((), |_, (t, coef)| add_term(&mut terms, t, coef.checked_neg()?))
+            .and_then(|_| c.checked_sub(rhs_c));
+        let c = match moved {
+            Some(c) => c,
+            None => {
+                self.emitter.borrow_mut().add(Diag::DimOverflow(a.clone(), a.span()));
+                return;
+            }
+        };
+
+        match terms.len() {
+            0 if c == 0 => (),
+            1 if c.checked_rem(terms[0].1) == Some(0) => {
+                let (ref t, coef) = terms[0];
+                if let (DIM(..), Some(n)) = (t, c.checked_neg().and_then(|c| c.checked_div(coef))) {
+                    self.unify_var(t.clone(), ResolvedDim(n, a.span()))
+                } else {
+                    // e.g. `H / 2 = 3` has more than one solution
+                    self.postponed.push(Equals(a, b));
+                }
+            }
+            0 | 1 => {
+                self.emitter.borrow_mut().add(Diag::DimensionMismatch(a, b));
+            }
+            _ => {
+                self.postponed.push(Equals(a, b));
+            }
+        }
+    }
 }
 
-fn occurs(tvar: TypeId, ty: &Type) -> bool {
+fn all_ints(tys: &[Type]) -> bool {
+    tys.iter().all(|t| if let Type::INT(_) = t { true } else { false })
+}
+
+fn occurs(var: &Type, ty: &Type) -> bool {
     use self::Type::*;
     match ty {
-        FUN(_,_, ref p, ref r, _) => occurs(tvar, &p) | occurs(tvar, &r),
-        VAR(ref tvar2, _) => tvar == *tvar2,
+        FUN(_,_, ref p, ref r, _) => occurs(var, &p) | occurs(var, &r),
+        VAR(..) | DIM(..) | DIMS(..) => var == ty,
+        DimExpr(_, ref l, ref r, _) => occurs(var, &l) | occurs(var, &r),
+        TSR(ref dims, ref dt, _) => dims.iter().any(|d| occurs(var, d)) | occurs(var, dt),
         _ => false,
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Substitution(pub BTreeMap<Type, Type>);
-
-impl Substitution {
+/// a dimension written as `sum(coef * term) + c` where each term is
+/// either a `DIM` or a nonlinear expression such as `H / 2`
+struct LinearForm(Vec<(Type, i64)>, i64);
 
-    /// apply substitution to a set of constraints
-    pub fn apply(&mut self, cs: &Constraints) -> Constraints {
-        Constraints {
-            set: cs.set
-                .iter()
-                .map(|Equals(a, b)| Equals(self.apply_ty(a), self.apply_ty(b)))
-                .collect(),
-            tenv: cs.tenv.clone(),
-            emitter: cs.emitter.clone(),
-        }
+/// returns None if the coefficient overflows
+fn add_term(terms: &mut Vec<(Type, i64)>, t: Type, coef: i64) -> Option<()> {
+    match terms.iter().position(|(t2, _)| *t2 == t) {
+        Some(i) => terms[i].1 = terms[i].1.checked_add(coef)?,
+        None => terms.push((t, coef)),
     }
+    terms.retain(|(_, coef)| *coef != 0);
+    Some(())
+}
 
-    pub fn apply_ty(&mut self, ty: &Type) -> Type {
-        self.0.iter().fold(ty.clone(), |result, solution| {
-            let (ty, solution_type) = solution;
-            if let Type::VAR(ref tvar, ref span) = ty {
-                substitute_tvar(result, tvar, &solution_type.with_span(span))
-            } else {
-                panic!("Impossible!");
+/// scales every coefficient and the constant by `k`
+fn scale(terms: Vec<(Type, i64)>, c: i64, k: i64) -> Option<LinearForm> {
+    let terms = terms.into_iter()
+        .map(|(t, v)| Some((t, v.checked_mul(k)?)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(LinearForm(terms, c.checked_mul(k)?))
+}
+
+/// returns None if the type is not a dimension
+fn linear_form(ty: &Type) -> Option<Result<LinearForm, Diag>> {
+    use self::Type::*;
+    use parsing::term::Op;
+    let overflow = || Some(Err(Diag::DimOverflow(ty.clone(), ty.span())));
+    match ty {
+        ResolvedDim(n, _) => Some(Ok(LinearForm(vec![], *n))),
+        DIM(..) => Some(Ok(LinearForm(vec![(ty.clone(), 1)], 0))),
+        DimExpr(op, l, r, _) => {
+            let LinearForm(mut v1, c1) = match linear_form(l)? {
+                Ok(form) => form,
+                Err(e) => return Some(Err(e)),
+            };
+            let LinearForm(v2, c2) = match linear_form(r)? {
+                Ok(form) => form,
+                Err(e) => return Some(Err(e)),
+            };
+            let form = match op {
+                Op::Add | Op::Sub => {
+                    let sign = if *op == Op::Add { 1 } else { -1 };
+                    let added = v2.into_iter()
+                        .try_fold((), |_, (t, coef)| add_term(&mut v1, t, coef.checked_mul(sign)?));
+                    added
+                        .and_then(|_| c1.checked_add(c2.checked_mul(sign)?))
+                        .map(|c| LinearForm(v1, c))
+                }
+                Op::Mult if v1.is_empty() => scale(v2, c2, c1),
+                Op::Mult if v2.is_empty() => scale(v1, c1, c2),
+                _ => Some(LinearForm(vec![(ty.clone(), 1)], 0)),
+            };
+            match form {
+                Some(form) => Some(Ok(form)),
+                None => overflow(),
             }
-        })
+        }
+        _ => None,
     }
+}
 
-    pub fn compose(&mut self, mut other: Substitution) -> Substitution {
-        let mut self_substituded: BTreeMap<Type, Type> = self.0
-            .clone()
-            .into_iter()
-            .map(|(k, s)| (k, other.apply_ty(&s)))
-            .collect();
-        self_substituded.extend(other.0);
-        Substitution(self_substituded)
-    }
+#[derive(Debug, PartialEq)]
+pub struct Substitution(pub BTreeMap<Type, Type>);
+
+impl Substitution {
 
-    pub fn empty() -> Substitution {
-        Substitution(BTreeMap::new())
+    /// every variable is mapped to a fully resolved type so one pass is enough
+    pub fn apply_ty(&mut self, ty: &Type) -> Type {
+        substitute(ty.clone(), &self.0)
     }
 }
 
-/// replace tvar with replacement in ty
-fn substitute_tvar(ty: Type, tvar: &TypeId, replacement: &Type) -> Type {
+/// replace the type variables in ty that have a solution
+fn substitute(ty: Type, solutions: &BTreeMap<Type, Type>) -> Type {
     use self::Type::*;
-    // println!("\nTVAR:::\n{:?}, \n'{:?}, \n{:?}\n", ty, tvar, replacement);
     match ty {
         UnresolvedModuleFun(_, _, _, _) => {
-            println!("{:?}, replacement: {:?}", ty, replacement);
+            println!("{:?}", ty);
             ty
         },
         Unit(_) => ty,
@@ -265,44 +601,88 @@ fn substitute_tvar(ty: Type, tvar: &TypeId, replacement: &Type) -> Type {
         BOOL(_) => ty,
         FLOAT(_) => ty,
         ResolvedDim(_, _) => ty,
-        VAR(tvar2, span) => {
-            if *tvar == tvar2 {
-                replacement.with_span(&span)
-            } else {
-                ty
-            }
-        }
-        DIM(tvar2, span) => {
-            if *tvar == tvar2 {
-                replacement.with_span(&span)
-            } else {
-                ty
+        VAR(_, span) | DIM(_, span) | DIMS(_, span) => {
+            match solutions.get(&ty.with_span(&CSpan::fresh_span())) {
+                Some(solution) => solution.with_span(&span),
+                None => ty,
             }
         }
+        DimExpr(op, l, r, s) => Type::dim_op(
+            op,
+            substitute(*l, solutions),
+            substitute(*r, solutions),
+            s,
+        ),
         FnArgs(args, span) => FnArgs(
             args.into_iter()
                 .map(|ty| match ty {
-                    FnArg(name, a, s) => FnArg(name, box substitute_tvar(*a, tvar, replacement), s),
+                    FnArg(name, a, s) => FnArg(name, box substitute(*a, solutions), s),
                     _ => panic!(ty),
                 })
                 .collect(),
             span,
         ),
-        Tuple(tys, s) => Tuple(tys.into_iter().map(|t| substitute_tvar(t, tvar, replacement)).collect(), s),
+        Tuple(tys, s) => Tuple(tys.into_iter().map(|t| substitute(t, solutions)).collect(), s),
         FUN(module,name,p, r, s) => FUN(
             module,
             name,
-            box substitute_tvar(*p, tvar, &replacement),
-            box substitute_tvar(*r, tvar, &replacement),
+            box substitute(*p, solutions),
+            box substitute(*r, solutions),
+            s,
+        ),
+        // a bound ellipsis is spliced into the surrounding tensor
+        TSR(dims, dt, s) => TSR(
+            dims.into_iter()
+                .flat_map(|t| match substitute(t, solutions) {
+                    TSR(ds, _, _) => ds,
+                    d => vec![d],
+                })
+                .collect(),
+            box substitute(*dt, solutions),
             s,
         ),
-        TSR(_, _) => ty,
+        DTYPE(..) => ty,
 
-        Module(n, Some(box ty), s) => {
-            Module(n, Some(box substitute_tvar(ty, tvar, replacement)), s)
+        Module(n, Some(box ty), count, s) => {
+            Module(n, Some(box substitute(ty, solutions)), count, s)
         }
 
-        Module(_, None, _) => ty,
-        FnArg(name, box ty, s) => FnArg(name, box substitute_tvar(ty, tvar, replacement), s),
+        Module(_, None, ..) => ty,
+        FnArg(name, box ty, s) => FnArg(name, box substitute(ty, solutions), s),
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan::{ByteIndex, CodeMap, Span};
+    use core::Core;
+    use parsing::term::DType;
+
+    /// unifies `x |> (+ b0) |> ... |> (+ b{n-1})`, where each stage
+    /// is a binary op that waits on the output of the one before
+    fn pipeline_steps(n: usize) -> usize {
+        let sp = Span::new(ByteIndex(1), ByteIndex(1));
+        let emitter = Rc::new(RefCell::new(Emitter::new(CodeMap::new(), false)));
+        let tenv = Rc::new(RefCell::new(TypeEnv::new(Rc::new(RefCell::new(Core::new())))));
+        let tsr = |b| Type::TSR(vec![b, Type::ResolvedDim(8, sp)], box Type::DTYPE(DType::F32, sp), sp);
+        let mut cs = Constraints::new(Rc::clone(&emitter), Rc::clone(&tenv));
+        cs.set.insert(Equals(Type::VAR(0, sp), tsr(Type::DIM(0, sp))));
+        for i in 0..n {
+            let (x, bias, out) = (Type::VAR(2 * i, sp), Type::VAR(2 * i + 1, sp), Type::VAR(2 * i + 2, sp));
+            cs.set.insert(Equals(bias.clone(), tsr(Type::ResolvedDim(1, sp))));
+            cs.deferred.push(Deferred::BinOp(out, x, bias, sp));
+        }
+        let mut unifier = Unifier::new(Rc::clone(&emitter), tenv);
+        let sub = unifier.solve(cs);
+        let out = sub.0.iter().find(|(var, _)| **var == Type::VAR(2 * n, sp)).map(|(_, ty)| ty);
+        assert_eq!(out, Some(&tsr(Type::DIM(0, sp))));
+        unifier.steps
+    }
+
+    #[test]
+    fn should_unify_deep_pipelines_in_linear_steps() {
+        let small = pipeline_steps(250);
+        let large = pipeline_steps(500);
+        assert!(large <= small * 2, "250 stages: {} steps, 500 stages: {} steps", small, large);
+    }
+}
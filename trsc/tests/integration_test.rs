@@ -35,4 +35,486 @@ fn test_gan() {
         .and()
         .stdout().is(include_str!("output/gan.py"))
         .unwrap();
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_residual() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/residual.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/residual.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_dim_arith() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/dim_arith.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/dim_arith.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_conv_generic() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_generic.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/conv_generic.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_let_branch() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/let_branch.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/let_branch.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_cond() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/cond.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/cond.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_repeat() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/repeat.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/repeat.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_alias() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/alias.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/alias.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_siamese() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/siamese.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/siamese.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_vae() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/vae.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/vae.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_gan_composed() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/gan_composed.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/gan_composed.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_multi_file() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/multi_file.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/multi_file.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_generic() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/generic.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/generic.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_ellipsis() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/ellipsis.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/ellipsis.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_dtype() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/dtype.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/dtype.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_unresolved_shape() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/unresolved_shape.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Could not infer shape of `view` from input [...1, <10>]")
+        .unwrap();
+}
+
+#[test]
+fn test_dim_origin() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/dim_origin.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`32` comes from here")
+        .and()
+        .stderr().contains("`64` comes from here")
+        .unwrap();
+}
+
+#[test]
+fn test_channel_mismatch() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/channel_mismatch.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Channel mismatch: expected <12> input channels but the input has <10>")
+        .unwrap();
+}
+
+#[test]
+fn test_conv_params() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_params.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/conv_params.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_conv_groups() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_groups.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`groups=3` does not divide `in_ch=8`")
+        .unwrap();
+}
+
+#[test]
+fn test_dcgan() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/dcgan.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/dcgan.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_conv_nd() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_nd.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/conv_nd.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_conv_rank() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_rank.trs"])
+        .fails()
+        .and()
+        .stderr().contains("rank 3 comes from here")
+        .unwrap();
+}
+
+#[test]
+fn test_pool() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/pool.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/pool.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_rnn() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/rnn.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/rnn.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_rnn_input_size() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/rnn_input_size.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`16` comes from here")
+        .unwrap();
+}
+
+#[test]
+fn test_embedding() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/embedding.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/embedding.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_embedding_padding() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/embedding_padding.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`padding_idx=1000` is out of range -1000..1000")
+        .unwrap();
+}
+
+#[test]
+fn test_embedding_dtype() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/embedding_dtype.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Tensor dtype mismatch: i64 != f32")
+        .unwrap();
+}
+
+#[test]
+fn test_wrong_arg_name() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/wrong_arg_name.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Wrong argument name: expected `target` but `label` was supplied")
+        .unwrap();
+}
+
+#[test]
+fn test_init_args() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/init_args.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`Linear` has no parameter `outt`")
+        .and()
+        .stderr().contains("Initialize `Conv2d` with parameter `out_ch=`")
+        .and()
+        .stderr().contains("Parameter `kernel_size` of `Conv2d` expects (int, int) but (int, int, int) was supplied")
+        .unwrap();
+}
+
+/// writes a single pipeline `x |> fc0 |> ... |> fc{n-1}` of `n` linear layers
+/// to a temporary file
+fn deep_pipeline(n: usize) -> std::path::PathBuf {
+    let mut src = String::from("use lin::Linear;\n\n");
+    let sig = "<(x: [?, 8]) -> [?, 8]>";
+    src += &format!("node Deep{} {{}}\nweights Deep{} {{\n", sig, sig);
+    for i in 0..n {
+        src += &format!("    fc{} = Linear::new(in=8, out=8);\n", i);
+    }
+    src += &format!("}}\ngraph Deep{} {{\n    def new() -> Self {{\n        self\n    }}\n    def forward {{\n        x", sig);
+    for i in 0..n {
+        src += &format!(" |> fc{}", i);
+    }
+    src += "\n    }\n}\n";
+    let path = std::env::temp_dir().join(format!("trsc_deep_{}.trs", n));
+    std::fs::write(&path, src).unwrap();
+    path
+}
+
+#[test]
+fn test_deep_pipeline() {
+    let path = deep_pipeline(500);
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", path.to_str().unwrap()])
+        .succeeds()
+        .and()
+        .stdout().contains("return self.fc499(x)")
+        .unwrap();
+}
+
+#[test]
+fn test_infer_in() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/infer_in.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/infer_in.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_broadcast_mismatch() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/broadcast.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Dimension mismatch: 10 != 3")
+        .unwrap();
+}
+
+#[test]
+fn test_tuple_arity() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/tuple_arity.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Type mismatch: ([!1, <20>], [!1, <20>]), ([!1, <20>], [!1, <20>], ")
+        .unwrap();
+}
+
+#[test]
+fn test_dim_overflow() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/dim_overflow.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Dimension overflows: (<9223372036854775807> * 2)")
+        .unwrap();
+}
+
+#[test]
+fn test_dim_div_zero() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/dim_div_zero.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Division by zero: (<8> / 0)")
+        .unwrap();
+}
+
+#[test]
+fn test_conv_stride() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_stride.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`stride=0` is less than 1")
+        .unwrap();
+}
+
+#[test]
+fn test_conv_kernel() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_kernel.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Input size 4 gives an output size of -2")
+        .unwrap();
+}
+
+#[test]
+fn test_module_index() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/module_index.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`blocks[3]` is out of range 0..3")
+        .and()
+        .stderr().contains("`blocks[7]` is out of range 0..3")
+        .and()
+        .stderr().contains("`blocks` is a list of 3 modules, pick one with `blocks[i]`")
+        .and()
+        .stderr().contains("`out` is not a list of modules and cannot be indexed")
+        .unwrap();
+}
+
+#[test]
+fn test_loop_range() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/loop_range.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Loop range 3..1 counts down")
+        .unwrap();
+}
+
+#[test]
+fn test_weights_index() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/weights_index.trs"])
+        .fails()
+        .and()
+        .stderr().contains("Symbol `j` not in scope")
+        .unwrap();
+}
+
+#[test]
+fn test_embedding_literal() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/embedding_literal.trs"])
+        .succeeds()
+        .and()
+        .stdout().is(include_str!("output/embedding_literal.py"))
+        .unwrap();
+}
+
+#[test]
+fn test_embedding_index() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/embedding_index.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`1000` is out of range 0..1000")
+        .unwrap();
+}
+
+#[test]
+fn test_conv_output_padding() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/conv_output_padding.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`output_padding=2` must be smaller than `stride=2` or `dilation=1`")
+        .unwrap();
+}
+
+#[test]
+fn test_pool_padding() {
+    assert_cli::Assert::main_binary()
+        .with_args(&["--in", "tests/input/pool_padding.trs"])
+        .fails()
+        .and()
+        .stderr().contains("`padding=2` must be at most half of `kernel_size=3`")
+        .unwrap();
+}
@@ -0,0 +1,20 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Alias(nn.Module):
+    '''Alias::forward([!1, <10>] -> [!1, <2>])'''
+    def __init__(self):
+        super(Alias, self).__init__()
+        self.fc1 = nn.Linear(in_features=10, out_features=20)
+        self.fc2 = nn.Linear(in_features=20, out_features=2)
+    def forward(self, x):
+        x = self.fc1(x)
+        x = F.relu(x)
+        x = self.fc2(x)
+        return F.log_softmax(x, dim)
+
+
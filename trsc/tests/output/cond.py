@@ -0,0 +1,28 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Cond(nn.Module):
+    '''Cond::forward([!1, <10>] -> [!1, <10>])'''
+    def __init__(self):
+        super(Cond, self).__init__()
+        self.fc1 = nn.Linear(in_features=10, out_features=10)
+        self.fc2 = nn.Linear(in_features=10, out_features=10)
+    def forward(self, x):
+        if self.training:
+            h = self.fc1(x)
+            h = F.relu(h)
+        else:
+            h = self.fc1(x)
+        if False:
+            return self.fc2(h)
+        elif self.training:
+            x = self.fc2(h)
+            return F.sigmoid(x)
+        else:
+            return h
+
+
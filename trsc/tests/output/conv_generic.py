@@ -0,0 +1,21 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Features(nn.Module):
+    '''Features::forward([!1, <3>, !3, !4] -> [!1, <20>, ((!3 / 2) - 6), ((!4 / 2) - 6)])'''
+    def __init__(self):
+        super(Features, self).__init__()
+        self.conv1 = nn.Conv2d(in_channels=3, out_channels=10, kernel_size=5)
+        self.conv2 = nn.Conv2d(in_channels=10, out_channels=20, kernel_size=5)
+    def forward(self, x):
+        x = self.conv1(x)
+        x = F.max_pool2d(x, kernel_size=2)
+        x = F.relu(x)
+        x = self.conv2(x)
+        return F.relu(x)
+
+
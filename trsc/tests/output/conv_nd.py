@@ -0,0 +1,31 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Signal(nn.Module):
+    '''Signal::forward([!1, <2>, <100>] -> [!1, <2>, <100>])'''
+    def __init__(self):
+        super(Signal, self).__init__()
+        self.down = nn.Conv1d(in_channels=2, out_channels=16, kernel_size=5, stride=2, padding=2)
+        self.up = nn.ConvTranspose1d(in_channels=16, out_channels=2, kernel_size=5, stride=2, padding=2, output_padding=1)
+    def forward(self, x):
+        x = self.down(x)
+        x = F.relu(x)
+        return self.up(x)
+
+
+class Volume(nn.Module):
+    '''Volume::forward([!2, <1>, <16>, <32>, <32>] -> [!2, <1>, <16>, <32>, <32>])'''
+    def __init__(self):
+        super(Volume, self).__init__()
+        self.down = nn.Conv3d(in_channels=1, out_channels=8, kernel_size=(3, 4, 4), stride=(1, 2, 2), padding=1)
+        self.up = nn.ConvTranspose3d(in_channels=8, out_channels=1, kernel_size=(3, 4, 4), stride=(1, 2, 2), padding=1, groups=1)
+    def forward(self, x):
+        x = self.down(x)
+        x = F.relu(x)
+        return self.up(x)
+
+
@@ -0,0 +1,23 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class ConvParams(nn.Module):
+    '''ConvParams::forward([!1, <4>, <32>, <32>] -> [!1, <10>])'''
+    def __init__(self):
+        super(ConvParams, self).__init__()
+        self.conv1 = nn.Conv2d(in_channels=4, out_channels=8, kernel_size=3, stride=2, padding=1)
+        self.conv2 = nn.Conv2d(in_channels=8, out_channels=8, kernel_size=(3, 5), padding=(1, 2), dilation=2, groups=4, bias=False)
+        self.fc = nn.Linear(in_features=1344, out_features=10)
+    def forward(self, x):
+        x = self.conv1(x)
+        x = F.relu(x)
+        x = self.conv2(x)
+        x = F.relu(x)
+        x = x.view(-1, 1344)
+        return self.fc(x)
+
+
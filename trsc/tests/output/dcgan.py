@@ -0,0 +1,40 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Discriminator(nn.Module):
+    '''Discriminator::forward([!1, <1>, <28>, <28>] -> [!1, <1>])'''
+    def __init__(self):
+        super(Discriminator, self).__init__()
+        self.conv1 = nn.Conv2d(in_channels=1, out_channels=32, kernel_size=4, stride=2, padding=1)
+        self.conv2 = nn.Conv2d(in_channels=32, out_channels=64, kernel_size=4, stride=2, padding=1)
+        self.lin = nn.Linear(in_features=3136, out_features=1)
+    def forward(self, x):
+        x = self.conv1(x)
+        x = F.leaky_relu(x)
+        x = self.conv2(x)
+        x = F.leaky_relu(x)
+        x = x.view(-1, 3136)
+        x = self.lin(x)
+        return F.sigmoid(x)
+
+
+class Generator(nn.Module):
+    '''Generator::forward([!1, <100>, <1>, <1>] -> [!1, <1>, <28>, <28>])'''
+    def __init__(self):
+        super(Generator, self).__init__()
+        self.deconv1 = nn.ConvTranspose2d(in_channels=100, out_channels=128, kernel_size=7, bias=False)
+        self.deconv2 = nn.ConvTranspose2d(in_channels=128, out_channels=64, kernel_size=4, stride=2, padding=1, bias=False)
+        self.deconv3 = nn.ConvTranspose2d(in_channels=64, out_channels=1, kernel_size=4, stride=2, padding=1)
+    def forward(self, x):
+        x = self.deconv1(x)
+        x = F.relu(x)
+        x = self.deconv2(x)
+        x = F.relu(x)
+        x = self.deconv3(x)
+        return F.tanh(x)
+
+
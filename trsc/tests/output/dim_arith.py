@@ -0,0 +1,20 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Flatten(nn.Module):
+    '''Flatten::forward([!1, <3>, <8>, <8>] -> [!1, <24>])'''
+    def __init__(self):
+        super(Flatten, self).__init__()
+        self.fc1 = nn.Linear(in_features=192, out_features=96)
+        self.fc2 = nn.Linear(in_features=96, out_features=24)
+    def forward(self, x):
+        x = x.view(-1, 192)
+        x = self.fc1(x)
+        x = F.relu(x)
+        return self.fc2(x)
+
+
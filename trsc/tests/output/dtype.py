@@ -0,0 +1,21 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Classifier(nn.Module):
+    '''Classifier::forward((x: [!1, <20>]: i64, target: [!1]: i64) -> [])'''
+    def __init__(self):
+        super(Classifier, self).__init__()
+        self.fc1 = nn.Linear(in_features=20, out_features=32)
+        self.fc2 = nn.Linear(in_features=32, out_features=10)
+    def forward(self, x, target):
+        x = x.to(torch.float32)
+        x = self.fc1(x)
+        x = F.relu(x)
+        x = self.fc2(x)
+        return F.cross_entropy(x, target)
+
+
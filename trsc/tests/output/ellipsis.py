@@ -0,0 +1,31 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Head(nn.Module):
+    '''Head::forward([!2, <20>, <128>] -> [!2, <20>, <17>])'''
+    def __init__(self):
+        super(Head, self).__init__()
+        self.fc = nn.Linear(in_features=128, out_features=17)
+    def forward(self, x):
+        x = self.fc(x)
+        return F.sigmoid(x)
+
+
+class Tagger(nn.Module):
+    '''Tagger::forward([!2, <20>, <300>] -> [!2, <20>, <17>])'''
+    def __init__(self):
+        super(Tagger, self).__init__()
+        self.norm = nn.LayerNorm(normalized_shape=300)
+        self.fc = nn.Linear(in_features=300, out_features=128)
+        self.head = Head()
+    def forward(self, x):
+        x = self.norm(x)
+        x = self.fc(x)
+        x = F.relu(x)
+        return self.head(x)
+
+
@@ -0,0 +1,19 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Tagger(nn.Module):
+    '''Tagger::forward((x: [!1, <12>]: i64) -> [!1, <12>, <5>])'''
+    def __init__(self, vectors):
+        super(Tagger, self).__init__()
+        self.emb = nn.Embedding(num_embeddings=1000, embedding_dim=50, padding_idx=0)
+        self.fc = nn.Linear(in_features=50, out_features=5)
+        self.emb.weight = nn.Parameter(vectors, requires_grad=True)
+    def forward(self, x):
+        x = self.emb(x)
+        return self.fc(x)
+
+
@@ -0,0 +1,16 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Lookup(nn.Module):
+    '''Lookup::forward((x: [!1, <1>]) -> [<3>, <50>])'''
+    def __init__(self):
+        super(Lookup, self).__init__()
+        self.emb = nn.Embedding(num_embeddings=1000, embedding_dim=50)
+    def forward(self, x):
+        return self.emb(torch.tensor((3, 14, 15)))
+
+
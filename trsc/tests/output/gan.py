@@ -6,7 +6,7 @@ import torch.optim as optim
 
 
 class Discriminator(nn.Module):
-    '''Discriminator::forward([!1, <1>, <28>, <28>] -> [!4, <1>])'''
+    '''Discriminator::forward([!1, <1>, <28>, <28>] -> [!1, <1>])'''
     def __init__(self):
         super(Discriminator, self).__init__()
         self.lin1 = nn.Linear(in_features=784, out_features=512)
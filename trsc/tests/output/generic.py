@@ -0,0 +1,30 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Ensemble(nn.Module):
+    '''Ensemble::forward([!5, <784>] -> [!5, <10>])'''
+    def __init__(self):
+        super(Ensemble, self).__init__()
+        self.body = Mlp(IN=784, HIDDEN=128, OUT=64)
+        self.head = Mlp(IN=64, HIDDEN=32, OUT=10)
+    def forward(self, x):
+        x = self.body(x)
+        return self.head(x)
+
+
+class Mlp(nn.Module):
+    '''Mlp::forward([!4, !1] -> [!4, !3])'''
+    def __init__(self, IN, HIDDEN, OUT):
+        super(Mlp, self).__init__()
+        self.fc1 = nn.Linear(in_features=IN, out_features=HIDDEN)
+        self.fc2 = nn.Linear(in_features=HIDDEN, out_features=OUT)
+    def forward(self, x):
+        x = self.fc1(x)
+        x = F.relu(x)
+        return self.fc2(x)
+
+
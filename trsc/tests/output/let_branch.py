@@ -0,0 +1,24 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class TwoBranch(nn.Module):
+    '''TwoBranch::forward([!1, <10>] -> [!1, <5>])'''
+    def __init__(self):
+        super(TwoBranch, self).__init__()
+        self.fc1 = nn.Linear(in_features=10, out_features=20)
+        self.fc2 = nn.Linear(in_features=10, out_features=20)
+        self.out = nn.Linear(in_features=20, out_features=5)
+    def forward(self, x):
+        a = self.fc1(x)
+        a = F.relu(a)
+        b = self.fc2(x)
+        b = F.sigmoid(b)
+        h = a + b
+        h = h * 0.5
+        return self.out(h)
+
+
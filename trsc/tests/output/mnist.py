@@ -18,11 +18,11 @@ class Mnist(nn.Module):
         nn.init.normal_(std=1)
     def forward(self, x):
         x = self.conv1(x)
-        x = F.max_pool2d(x, kernel_size)
+        x = F.max_pool2d(x, kernel_size=2)
         x = F.relu(x)
         x = self.conv2(x)
         x = self.dropout(x)
-        x = F.max_pool2d(x, kernel_size)
+        x = F.max_pool2d(x, kernel_size=2)
         x = F.relu(x)
         x = x.view(-1, 320)
         x = self.fc1(x)
@@ -0,0 +1,28 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Classifier(nn.Module):
+    '''Classifier::forward([!1, <784>] -> [!1, <10>])'''
+    def __init__(self):
+        super(Classifier, self).__init__()
+        self.enc = Encoder()
+        self.out = nn.Linear(in_features=128, out_features=10)
+    def forward(self, x):
+        x = self.enc(x)
+        return self.out(x)
+
+
+class Encoder(nn.Module):
+    '''Encoder::forward([!1, <784>] -> [!1, <128>])'''
+    def __init__(self):
+        super(Encoder, self).__init__()
+        self.fc = nn.Linear(in_features=784, out_features=128)
+    def forward(self, x):
+        x = self.fc(x)
+        return F.relu(x)
+
+
@@ -0,0 +1,57 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Features(nn.Module):
+    '''Features::forward([!1, <3>, <32>, <32>] -> [!1, <10>])'''
+    def __init__(self):
+        super(Features, self).__init__()
+        self.conv1 = nn.Conv2d(in_channels=3, out_channels=16, kernel_size=3)
+        self.pool1 = nn.MaxPool2d(kernel_size=2)
+        self.conv2 = nn.Conv2d(in_channels=16, out_channels=64, kernel_size=3)
+        self.pool2 = nn.MaxPool2d(kernel_size=2, ceil_mode=True)
+        self.pool3 = nn.AdaptiveAvgPool2d(output_size=(3, 3))
+        self.fc = nn.Linear(in_features=576, out_features=10)
+    def forward(self, x):
+        x = self.conv1(x)
+        x = F.relu(x)
+        x = self.pool1(x)
+        x = self.conv2(x)
+        x = F.relu(x)
+        x = self.pool2(x)
+        x = F.avg_pool2d(x, kernel_size=3, stride=1, padding=1, count_include_pad=False)
+        x = self.pool3(x)
+        x = x.view(-1, 576)
+        return self.fc(x)
+
+
+class Head(nn.Module):
+    '''Head::forward([!2, <64>, <7>, <7>] -> [!2, <64>, <1>, <1>])'''
+    def __init__(self):
+        super(Head, self).__init__()
+    def forward(self, x):
+        x = F.adaptive_avg_pool2d(x, output_size=2)
+        return F.adaptive_avg_pool2d(x, 1)
+
+
+class Signal(nn.Module):
+    '''Signal::forward([!3, <8>, <100>] -> [!3, <8>, <17>])'''
+    def __init__(self):
+        super(Signal, self).__init__()
+        self.pool = nn.AvgPool1d(kernel_size=3, stride=2, padding=1)
+    def forward(self, x):
+        x = self.pool(x)
+        return F.max_pool1d(x, kernel_size=3, ceil_mode=True)
+
+
+class Volume(nn.Module):
+    '''Volume::forward([!4, <1>, <16>, <64>, <64>] -> [!4, <1>, <8>, <16>, <16>])'''
+    def __init__(self):
+        super(Volume, self).__init__()
+    def forward(self, x):
+        return F.max_pool3d(x, kernel_size=(2, 4, 4), dilation=1)
+
+
@@ -0,0 +1,22 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Deep(nn.Module):
+    '''Deep::forward([!1, <64>] -> [!1, <10>])'''
+    def __init__(self):
+        super(Deep, self).__init__()
+        self.blocks = nn.ModuleList([nn.Linear(in_features=64, out_features=64) for _ in range(3)])
+        self.out = nn.Linear(in_features=64, out_features=10)
+    def forward(self, x):
+        h = x
+        for i in range(3):
+            h = self.blocks[i](h)
+            h = F.relu(h)
+        x = self.blocks[0](h)
+        return self.out(x)
+
+
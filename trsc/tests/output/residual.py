@@ -0,0 +1,19 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Residual(nn.Module):
+    '''Residual::forward([!1, <10>] -> [!1, <10>])'''
+    def __init__(self):
+        super(Residual, self).__init__()
+        self.fc1 = nn.Linear(in_features=10, out_features=10)
+        self.fc2 = nn.Linear(in_features=10, out_features=10)
+    def forward(self, x):
+        _t0 = self.fc1(x)
+        _t0 = F.relu(_t0)
+        return self.fc2(_t0) + (x * 0.5)
+
+
@@ -0,0 +1,28 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Encoder(nn.Module):
+    '''Encoder::forward([<20>, !2, <32>] -> [<1>, !2, <48>])'''
+    def __init__(self):
+        super(Encoder, self).__init__()
+        self.gru = nn.GRU(input_size=32, hidden_size=48)
+    def forward(self, x):
+        out, h = self.gru(x)
+        return h
+
+
+class Tagger(nn.Module):
+    '''Tagger::forward([!1, <20>, <32>] -> [!1, <20>, <10>])'''
+    def __init__(self):
+        super(Tagger, self).__init__()
+        self.lstm = nn.LSTM(input_size=32, hidden_size=64, num_layers=2, bidirectional=True, batch_first=True)
+        self.fc = nn.Linear(in_features=128, out_features=10)
+    def forward(self, x):
+        out, (h, c) = self.lstm(x)
+        return self.fc(out)
+
+
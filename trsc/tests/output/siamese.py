@@ -0,0 +1,22 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Siamese(nn.Module):
+    '''Siamese::forward((a: [!1, <10>], b: [!1, <10>]) -> [!1, <1>])'''
+    def __init__(self):
+        super(Siamese, self).__init__()
+        self.enc = nn.Linear(in_features=10, out_features=4)
+        self.out = nn.Linear(in_features=4, out_features=1)
+    def forward(self, a, b):
+        ha = self.enc(a)
+        ha = F.relu(ha)
+        hb = self.enc(b)
+        hb = F.relu(hb)
+        a = ha - hb
+        return self.out(a)
+
+
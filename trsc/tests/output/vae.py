@@ -0,0 +1,30 @@
+import torch
+from torch.autograd import Variable
+import torch.nn as nn
+import torch.nn.functional as F
+import torch.optim as optim
+
+
+class Vae(nn.Module):
+    '''Vae::forward([!1, <784>] -> ([!1, <784>], [!1, <20>], [!1, <20>]))'''
+    def __init__(self):
+        super(Vae, self).__init__()
+        self.fc1 = nn.Linear(in_features=784, out_features=400)
+        self.fc21 = nn.Linear(in_features=400, out_features=20)
+        self.fc22 = nn.Linear(in_features=400, out_features=20)
+        self.fc3 = nn.Linear(in_features=20, out_features=400)
+        self.fc4 = nn.Linear(in_features=400, out_features=784)
+    def forward(self, x):
+        mu, logvar = self.encode(x)
+        z = mu + (logvar * 0.5)
+        recon = self.fc3(z)
+        recon = F.relu(recon)
+        recon = self.fc4(recon)
+        recon = F.sigmoid(recon)
+        return (recon, mu, logvar)
+    def encode(self, x):
+        h = self.fc1(x)
+        h = F.relu(h)
+        return (self.fc21(h), self.fc22(h))
+
+
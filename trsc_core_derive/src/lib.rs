@@ -12,7 +12,7 @@ use std::collections::BTreeMap;
 use proc_macro::TokenStream;
 use parser::{parse_decl, FnDecl};
 
-#[proc_macro_derive(Op, attributes(stateful, path, init_normal, new, forward))]
+#[proc_macro_derive(Op, attributes(stateful, path, init_normal, init_pretrained, new, forward))]
 pub fn derive(input: TokenStream) -> TokenStream {
     // Construct a string representation of the type definition
     let s = input.to_string();
@@ -40,6 +40,7 @@ fn impl_op(ast: &syn::DeriveInput) -> quote::Tokens {
     let path = get_path(&ast.attrs).unwrap_or_else(|| panic!("no path supplied"));
     let fn_decls = get_fn_decls(path, &fns);
     let ty_sigs = gen_ty_sigs(&fn_decls);
+    let defaults = gen_defaults(&fn_decls);
 
     quote! {
         impl Op for #name {
@@ -52,6 +53,9 @@ fn impl_op(ast: &syn::DeriveInput) -> quote::Tokens {
             fn is_stateful(&self) -> bool {
                 #stateful
             }
+            fn defaults(&self, fn_name: &str) -> Vec<(&'static str, &'static str)> {
+                #defaults
+            }
         }
     }
 }
@@ -75,6 +79,27 @@ fn gen_ty_sigs(decls: &[FnDecl]) -> quote::Tokens {
     }
 }
 
+fn gen_defaults(decls: &[FnDecl]) -> quote::Tokens {
+    let arms: Vec<quote::Tokens> = decls
+        .iter()
+        .filter(|d| !d.defaults.is_empty())
+        .map(|d| {
+            let name = &d.name;
+            let params: Vec<_> = d.defaults.iter().map(|(p, _)| p).collect();
+            let values: Vec<_> = d.defaults.iter().map(|(_, v)| v).collect();
+            quote! {
+                #name => vec![#((#params, #values)),*],
+            }
+        })
+        .collect();
+    quote! {
+        match fn_name {
+            #(#arms)*
+            _ => vec![],
+        }
+    }
+}
+
 fn gen_decl(fn_decl: &FnDecl) -> quote::Tokens {
     let name = &fn_decl.name;
     let path = &fn_decl.path;
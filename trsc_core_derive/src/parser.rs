@@ -4,6 +4,9 @@ use quote::{ToTokens, Tokens};
 pub enum Type {
     Float,
     Int,
+    Bool,
+    /// a tuple of ints such as `kernel_size`, a single int is also accepted
+    Ints(usize),
     Tsr,
     SelfTy,
     Unit,
@@ -18,8 +21,12 @@ impl Type {
             "int" => Int,
             "self" => SelfTy,
             "unit" => Unit,
+            "bool" => Bool,
             "tsr0" => Tsr,
-            _ => panic!("Unknown type"),
+            _ => match s.trim_left_matches("int").parse() {
+                Ok(n) if s.starts_with("int") => Ints(n),
+                _ => panic!("Unknown type"),
+            },
         }
     }
 }
@@ -30,6 +37,8 @@ impl ToTokens for Type {
         match self {
             Float => tokens.append(quote!{float!()}),
             Int => tokens.append(quote!{int!()}),
+            Bool => tokens.append(quote!{Type::BOOL(CSpan::fresh_span())}),
+            Ints(n) => tokens.append(quote!{Type::Tuple(vec![int!(); #n], CSpan::fresh_span())}),
             Unit => tokens.append(quote!{unit!()}),
             SelfTy => tokens.append(quote!{module!(self.get_name())}),
             _ => unimplemented!(),
@@ -42,6 +51,8 @@ pub struct FnDecl {
     pub resolved: bool,
     pub params: Vec<String>,
     pub tys: Vec<Type>,
+    /// optional parameters and their default values
+    pub defaults: Vec<(String, String)>,
     pub ret: Type,
     pub name: String,
     pub path: String,
@@ -56,6 +67,7 @@ pub enum Token {
     ARROW,
     QMARK,
     COMMA,
+    EQ,
 }
 
 pub fn parse_decl(path: &str, name: &str, decl: &str) -> FnDecl {
@@ -81,6 +93,7 @@ fn parse(path: &str, name: &str, toks: &[Token]) -> FnDecl {
         resolved: true,
         params: vec![],
         tys: vec![],
+        defaults: vec![],
         ret: self::Type::Float,
     };
 
@@ -107,6 +120,15 @@ fn parse(path: &str, name: &str, toks: &[Token]) -> FnDecl {
             } else {
                 panic!("No param type specified");
             }
+            // default value, `?` if it is inferred from the input
+            if let Some(EQ) = it.peek() {
+                it.next();
+                match it.next() {
+                    Some(WORD(ref value)) => ret.defaults.push((name.clone(), value.clone())),
+                    Some(QMARK) => ret.defaults.push((name.clone(), "?".to_owned())),
+                    _ => panic!("No default value specified"),
+                }
+            }
         } else if ARROW == *tok {
             // return type
             it.next();
@@ -148,10 +170,10 @@ fn lex(decl: &str) -> Vec<Token> {
             ' ' | '\n' => {
                 it.next();
             }
-            'A'...'z' => {
+            'A'...'z' | '0'...'9' => {
                 let mut buf = String::new();
                 while let Some(ch) = it.peek().cloned() {
-                    if ch.is_alphanumeric() || ch == '_' {
+                    if ch.is_alphanumeric() || ch == '_' || ch == '.' {
                         buf.push(ch);
                         it.next();
                     } else {
@@ -168,6 +190,10 @@ fn lex(decl: &str) -> Vec<Token> {
                 toks.push(COMMA);
                 it.next();
             }
+            '=' => {
+                toks.push(EQ);
+                it.next();
+            }
             '-' => {
                 it.next();
                 if let Some('>') = it.next() {